@@ -92,6 +92,13 @@ impl FromStr for Color {
     }
 }
 
+#[cfg(feature = "fuzzing")]
+pub fn fuzz_parsers(input: &str) {
+    for line in input.lines() {
+        let _ = parsers::game(line);
+    }
+}
+
 mod parsers {
     use super::*;
     use nom::{
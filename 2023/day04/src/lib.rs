@@ -77,6 +77,14 @@ pub fn part2_rayon(input: &str) -> u32 {
     cards
 }
 
+#[cfg(feature = "fuzzing")]
+pub fn fuzz_parsers(input: &str) {
+    for line in input.lines() {
+        let _ = parsers::part1(line);
+        let _ = parsers::part2(line);
+    }
+}
+
 mod parsers {
     use nom::{
         bytes::complete::take_until,
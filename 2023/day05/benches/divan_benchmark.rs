@@ -28,3 +28,22 @@ mod part2 {
             .bench_values(|input| Day::part2(input))
     }
 }
+
+mod generated {
+    use day05::generator;
+
+    use super::*;
+    #[divan::bench]
+    fn part2_1000_seed_ranges(bencher: Bencher) {
+        bencher
+            .with_inputs(|| generator::input(0, 1000, 50))
+            .bench_values(|input| Day::part2(&input))
+    }
+
+    #[divan::bench]
+    fn part2_1000_translations(bencher: Bencher) {
+        bencher
+            .with_inputs(|| generator::input(0, 10, 1000))
+            .bench_values(|input| Day::part2(&input))
+    }
+}
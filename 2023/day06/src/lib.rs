@@ -33,10 +33,17 @@ pub fn brute_force(time: u64, distance: u64) -> usize {
         .count()
 }
 
+#[cfg(feature = "fuzzing")]
+pub fn fuzz_parsers(input: &str) {
+    let _ = parsers::part1(input);
+    let _ = parsers::part2(input);
+}
+
 mod parsers {
     use nom::{bytes::complete::tag, character::complete::u32};
     use nom::{
         character::complete::{digit1, line_ending, space1},
+        combinator::fail,
         multi::separated_list1,
         IResult,
     };
@@ -80,10 +87,14 @@ mod parsers {
                 Ok((number, digits.len() as u32))
             }),
         )(input)?;
-        let total = numbers
-            .into_iter()
-            .fold(0_u64, |acc, (num, len)| acc * 10_u64.pow(len) + num as u64);
-        Ok((input, total))
+        let total = numbers.into_iter().try_fold(0_u64, |acc, (num, len)| {
+            acc.checked_mul(10_u64.checked_pow(len)?)?
+                .checked_add(num as u64)
+        });
+        match total {
+            Some(total) => Ok((input, total)),
+            None => fail(input),
+        }
     }
 }
 
@@ -195,6 +195,14 @@ impl Hand {
     }
 }
 
+#[cfg(feature = "fuzzing")]
+pub fn fuzz_parsers(input: &str) {
+    for line in input.lines() {
+        let _ = parsers::part1(line);
+        let _ = parsers::part2(line);
+    }
+}
+
 mod parsers {
     use nom::{
         bytes::complete::take,
@@ -249,7 +249,7 @@ fn loop_length_encoded(start_id: u16, directions: &[Direction], nodes: &[[u16; 2
             Direction::Left => choice[0],
             Direction::Right => choice[1],
         };
-        if id == start_id && step != 0 {
+        if id == start_id {
             return step as u64 + 1;
         }
     }
@@ -277,6 +277,11 @@ fn encode(str: &str) -> u16 {
     c0 | c1 | c2
 }
 
+/// [`encode`] only supports ids made of 3 uppercase letters
+fn encodable(id: &str) -> bool {
+    id.len() == 3 && id.bytes().all(|b| b.is_ascii_uppercase())
+}
+
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum Direction {
     Left,
@@ -293,6 +298,15 @@ impl TryFrom<char> for Direction {
     }
 }
 
+/// The array parser also checks that every id is `encodable` before `encode` indexes the nodes with it
+#[cfg(feature = "fuzzing")]
+pub fn fuzz_parsers(input: &str) {
+    let _ = parsers::part1(input);
+    let _ = parsers::part1_str(input);
+    let _ = parsers::part1_btree_str(input);
+    let _ = parsers::part2_array(input);
+}
+
 mod parsers {
     use std::collections::HashMap;
 
@@ -397,7 +411,9 @@ mod parsers {
         let mut starts = Vec::new();
         let mut input = input;
         while !input.is_empty() {
-            let (remain, (id, directions)) = node_str(input)?;
+            let (remain, (id, directions)) = node_str
+                .verify(|(id, [left, right])| [id, left, right].into_iter().all(|id| encodable(id)))
+                .parse(input)?;
             let index = encode(id);
             if id.ends_with('Z') {
                 starts.push(index);
@@ -462,4 +478,85 @@ mod tests {
         let input = Day::INPUT;
         assert_eq!(15746133679061, part2_encoded(input))
     }
+
+    mod proptests {
+        use super::*;
+        use proptest::prelude::*;
+
+        /// Node names made of uppercase letters only, so that `encode` accepts them.
+        /// The last letter is never 'A' or 'Z'
+        fn node_name(index: usize) -> String {
+            let letter = |n: usize| (b'A' + n as u8) as char;
+            let last = letter(1 + index % 24);
+            let index = index / 24;
+            format!("{}{}{last}", letter(index / 26 % 26), letter(index % 26))
+        }
+
+        /// Generates inputs with the same structure as the real puzzle :
+        /// every ghost runs on its own loop, whose length is a multiple of the directions length,
+        /// and each `..Z` node has the same children as its `..A` node.
+        fn puzzle_input() -> impl Strategy<Value = String> {
+            (
+                prop::collection::vec(prop::bool::ANY, 1..=12),
+                prop::collection::vec(1..=6_usize, 1..=4),
+            )
+                .prop_flat_map(|(directions, multipliers)| {
+                    let chain_length = multipliers.iter().sum::<usize>() * directions.len();
+                    let other_sides =
+                        prop::collection::vec(any::<prop::sample::Index>(), chain_length);
+                    (Just(directions), Just(multipliers), other_sides)
+                })
+                .prop_map(|(directions, multipliers, other_sides)| {
+                    let mut other_sides = other_sides.into_iter();
+                    let mut names = (0..).map(node_name);
+                    let mut input = directions
+                        .iter()
+                        .map(|left| if *left { 'L' } else { 'R' })
+                        .collect::<String>();
+                    input.push_str("\n\n");
+
+                    for (ghost, multiplier) in multipliers.into_iter().enumerate() {
+                        let prefix = &node_name(ghost * 24)[..2];
+                        let length = multiplier * directions.len();
+                        let chain = std::iter::once(format!("{prefix}A"))
+                            .chain(names.by_ref().take(length - 1))
+                            .chain(std::iter::once(format!("{prefix}Z")))
+                            .collect::<Vec<_>>();
+
+                        let mut lines = Vec::new();
+                        for step in 0..length {
+                            let next = chain[step + 1].clone();
+                            let other = other_sides.next().unwrap().get(&chain).clone();
+                            let children = if directions[step % directions.len()] {
+                                [next, other]
+                            } else {
+                                [other, next]
+                            };
+                            lines.push((chain[step].clone(), children));
+                        }
+                        // The `..Z` node leads back into the loop, exactly like the `..A` node
+                        lines.push((chain[length].clone(), lines[0].1.clone()));
+
+                        for (id, [left, right]) in lines {
+                            input.push_str(&format!("{id} = ({left}, {right})\n"));
+                        }
+                    }
+                    input
+                })
+        }
+
+        proptest! {
+            #[test]
+            fn part2_variants_agree(input in puzzle_input()) {
+                let expected = Day::part2(&input);
+                prop_assert_eq!(expected, part2_hash_string_singlethread(&input));
+                prop_assert_eq!(expected, part2_hash_str(&input));
+                prop_assert_eq!(expected, part2_hash_str_singlethread(&input));
+                prop_assert_eq!(expected, part2_btree_str(&input));
+                prop_assert_eq!(expected, part2_btree_str_singlethread(&input));
+                prop_assert_eq!(expected, part2_encoded(&input));
+                prop_assert_eq!(expected, part2_encoded_singlethreaded(&input));
+            }
+        }
+    }
 }
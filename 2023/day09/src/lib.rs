@@ -144,6 +144,13 @@ mod predict_next {
     }
 }
 
+#[cfg(feature = "fuzzing")]
+pub fn fuzz_parsers(input: &str) {
+    for line in input.lines() {
+        let _ = parsers::values(line);
+    }
+}
+
 mod parsers {
     use nom::{
         character::complete::{i32, space1},
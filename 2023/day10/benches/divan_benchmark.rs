@@ -17,6 +17,13 @@ mod part1 {
             .with_inputs(|| Day::INPUT)
             .bench_values(|input| Day::part1(input))
     }
+
+    #[divan::bench]
+    fn hashmap(bencher: Bencher) {
+        bencher
+            .with_inputs(|| Day::INPUT)
+            .bench_values(|input| day10::hashmap::part1(input))
+    }
 }
 
 mod part2 {
@@ -27,4 +34,11 @@ mod part2 {
             .with_inputs(|| Day::INPUT)
             .bench_values(|input| Day::part2(input))
     }
+
+    #[divan::bench]
+    fn hashmap(bencher: Bencher) {
+        bencher
+            .with_inputs(|| Day::INPUT)
+            .bench_values(|input| day10::hashmap::part2(input))
+    }
 }
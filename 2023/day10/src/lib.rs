@@ -12,13 +12,26 @@ impl Aoc for Day {
     const SAMPLE_PART2: &'static str = include_str!("../inputs/sample2.txt");
 
     fn part1(input: &str) -> Self::OUTPUT {
+        Maze::new(input).farthest() as u32
+    }
+
+    fn part2(input: &str) -> Self::OUTPUT {
+        Maze::new(input).inside_count() as u32
+    }
+}
+
+/// First implementation, for benching purposes
+pub mod hashmap {
+    use super::*;
+
+    pub fn part1(input: &str) -> u32 {
         let map = parsers::part1_hashmap(input);
         let start = find_start(&map).expect("Starting location not found");
         let pipes = find_pipe_loop(start, &map);
         pipes.len() as u32 / 2
     }
 
-    fn part2(input: &str) -> Self::OUTPUT {
+    pub fn part2(input: &str) -> u32 {
         let map = parsers::part1_hashmap(input);
 
         let start = find_start(&map).expect("Starting location not found");
@@ -57,6 +70,164 @@ impl Aoc for Day {
     }
 }
 
+pub use maze::Maze;
+
+/// The maze on a dense grid, with the main loop walked once
+pub mod maze {
+    use super::*;
+
+    #[derive(Debug, Clone)]
+    pub struct Maze {
+        width: usize,
+        height: usize,
+        tiles: Vec<Option<Pipe>>,
+        /// Tiles of the main loop, from the start
+        path: Vec<(usize, usize)>,
+        on_loop: Vec<bool>,
+    }
+    impl Maze {
+        pub fn new(input: &str) -> Self {
+            let width = input.lines().map(str::len).max().unwrap_or(0);
+            let height = input.lines().count();
+            let mut tiles = vec![None; width * height];
+            for (y, line) in input.lines().enumerate() {
+                for (x, c) in line.char_indices() {
+                    tiles[y * width + x] = Pipe::try_from(c).ok();
+                }
+            }
+            let start = tiles
+                .iter()
+                .position(|tile| *tile == Some(Pipe::Start))
+                .map(|index| ((index % width) as i32, (index / width) as i32))
+                .expect("Starting location not found");
+
+            let mut maze = Self {
+                width,
+                height,
+                tiles,
+                path: Vec::new(),
+                on_loop: vec![false; width * height],
+            };
+            let path = maze.find_loop(start);
+            // The start is replaced by the pipe it stands for
+            let replacement = start_replacement(start, path[1], path[path.len() - 1]);
+            maze.tiles[start.1 as usize * width + start.0 as usize] = Some(replacement);
+            for (x, y) in &path {
+                maze.on_loop[*y as usize * width + *x as usize] = true;
+            }
+            maze.path = path
+                .into_iter()
+                .map(|(x, y)| (x as usize, y as usize))
+                .collect();
+            maze
+        }
+
+        fn pipe(&self, (x, y): Coords) -> Option<Pipe> {
+            if x < 0 || y < 0 || x as usize >= self.width || y as usize >= self.height {
+                return None;
+            }
+            self.tiles[y as usize * self.width + x as usize]
+        }
+
+        fn find_loop(&self, start: Coords) -> Vec<Coords> {
+            for mut direction in Direction::ALL {
+                let mut coords = start;
+                let mut pipes = vec![start];
+                loop {
+                    coords = direction.next_coords(coords);
+                    let Some(pipe) = self.pipe(coords) else {
+                        break;
+                    };
+                    if pipe == Pipe::Start {
+                        return pipes;
+                    }
+                    pipes.push(coords);
+                    let Some(next) = direction.next_direction(&pipe) else {
+                        break;
+                    };
+                    direction = next;
+                }
+            }
+            panic!("Searched all directions but did not find the loop")
+        }
+
+        /// Tiles of the main loop, in walking order from the start
+        pub fn path(&self) -> &[(usize, usize)] {
+            &self.path
+        }
+
+        /// Steps to the point of the loop farthest from the start
+        pub fn farthest(&self) -> usize {
+            self.path.len() / 2
+        }
+
+        /// Tiles enclosed by the loop, row by row.
+        /// Along a row, the inside flips on every loop pipe going up : `|`, `L` and `J`
+        pub fn inside(&self) -> Vec<(usize, usize)> {
+            let mut inside_tiles = Vec::new();
+            for y in 0..self.height {
+                let mut inside = false;
+                for x in 0..self.width {
+                    let index = y * self.width + x;
+                    if self.on_loop[index] {
+                        if matches!(
+                            self.tiles[index],
+                            Some(Pipe::Vertical | Pipe::BottomLeft | Pipe::BottomRight)
+                        ) {
+                            inside = !inside;
+                        }
+                    } else if inside {
+                        inside_tiles.push((x, y));
+                    }
+                }
+            }
+            inside_tiles
+        }
+
+        pub fn inside_count(&self) -> usize {
+            self.inside().len()
+        }
+
+        /// Cross-check of `inside_count` : the shoelace formula gives the area of the polygon
+        /// through the centres of the loop tiles, and Pick's theorem the number of tiles strictly inside
+        pub fn pick_count(&self) -> usize {
+            let double_area = self
+                .path
+                .iter()
+                .zip(self.path.iter().cycle().skip(1))
+                .map(|(a, b)| a.0 as i64 * b.1 as i64 - b.0 as i64 * a.1 as i64)
+                .sum::<i64>()
+                .unsigned_abs() as usize;
+            (double_area + 2 - self.path.len()) / 2
+        }
+
+        /// Heavy lines for the main loop, light lines for the other pipes, and shaded inside tiles
+        pub fn render(&self) -> String {
+            let inside = self.inside();
+            let mut shaded = vec![false; self.tiles.len()];
+            for (x, y) in inside {
+                shaded[y * self.width + x] = true;
+            }
+            let mut output = String::new();
+            for y in 0..self.height {
+                for x in 0..self.width {
+                    let index = y * self.width + x;
+                    let c = match self.tiles[index] {
+                        _ if (x, y) == self.path[0] => 'S',
+                        _ if shaded[index] => '▒',
+                        Some(pipe) if self.on_loop[index] => pipe.to_heavy_char(),
+                        Some(pipe) => pipe.to_char(),
+                        None => ' ',
+                    };
+                    output.push(c);
+                }
+                output.push('\n');
+            }
+            output
+        }
+    }
+}
+
 fn find_start(map: &HashMap<Coords, Pipe>) -> Option<Coords> {
     map.iter()
         .find_map(|(coords, pipe)| (*pipe == Pipe::Start).then_some(*coords))
@@ -158,7 +329,7 @@ impl Direction {
 }
 
 #[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
-enum Pipe {
+pub enum Pipe {
     Vertical,
     Horizontal,
     TopLeft,
@@ -195,6 +366,18 @@ impl Pipe {
             Pipe::Start => 'S',
         }
     }
+
+    fn to_heavy_char(self) -> char {
+        match self {
+            Pipe::Vertical => '┃',
+            Pipe::Horizontal => '━',
+            Pipe::TopLeft => '┏',
+            Pipe::TopRight => '┓',
+            Pipe::BottomLeft => '┗',
+            Pipe::BottomRight => '┛',
+            Pipe::Start => 'S',
+        }
+    }
 }
 impl std::fmt::Debug for Pipe {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -251,4 +434,37 @@ mod tests {
         let input = include_str!("../inputs/sample2.2.txt");
         assert_eq!(10, Day::part2(input));
     }
+
+    #[test]
+    fn test_maze() {
+        let samples = [
+            (Day::SAMPLE_PART2, 4),
+            (include_str!("../inputs/sample2.1.txt"), 8),
+            (include_str!("../inputs/sample2.2.txt"), 10),
+            (Day::INPUT, hashmap::part2(Day::INPUT) as usize),
+        ];
+        for (input, expected) in samples {
+            let maze = Maze::new(input);
+            assert_eq!(expected, maze.inside_count());
+            assert_eq!(expected, maze.pick_count());
+            assert_eq!(hashmap::part1(input) as usize, maze.farthest());
+        }
+
+        let maze = Maze::new(Day::SAMPLE_PART2);
+        assert_eq!(46, maze.path().len());
+        assert_eq!([(1, 1), (1, 2)], maze.path()[..2]);
+        assert_eq!(vec![(2, 6), (3, 6), (7, 6), (8, 6)], maze.inside());
+        let render = maze.render();
+        let lines = render.lines().collect::<Vec<_>>();
+        assert_eq!(" S━━━━━━━┓ ", lines[1]);
+        assert_eq!(" ┃▒▒┃ ┃▒▒┃ ", lines[6]);
+
+        // Pipes outside the loop are drawn with light lines, the ones inside are shaded
+        let maze = Maze::new("-L|F7\n7S-7|\nL|7||\n-L-J|\nL|-JF");
+        let render = maze.render();
+        let lines = render.lines().collect::<Vec<_>>();
+        assert_eq!("┐S━┓│", lines[1]);
+        assert_eq!("└┃▒┃│", lines[2]);
+        assert_eq!(1, maze.pick_count());
+    }
 }
@@ -1,6 +1,9 @@
 use aoc::Aoc;
-use day10::Day;
+use day10::{Day, Maze};
 
 fn main() {
-    Day::main()
+    match std::env::args().nth(1).as_deref() {
+        Some("render") => print!("{}", Maze::new(Day::INPUT).render()),
+        _ => Day::main(),
+    }
 }
@@ -27,4 +27,11 @@ mod part2 {
             .with_inputs(|| Day::INPUT)
             .bench_values(|input| Day::part2(input))
     }
+
+    #[divan::bench]
+    fn pairwise(bencher: Bencher) {
+        bencher
+            .with_inputs(|| Day::INPUT)
+            .bench_values(|input| day11::pairwise::total_distance(input, 1_000_000 - 1))
+    }
 }
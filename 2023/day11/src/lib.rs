@@ -11,44 +11,143 @@ impl Aoc for Day {
     const SAMPLE_PART2: &'static str = include_str!("../inputs/sample2.txt");
 
     fn part1(input: &str) -> Self::OUTPUT {
-        let mut coords = parsers::part1(input);
-        expand_universe(&mut coords, 1);
-        sum_distances(&coords)
+        usize::try_from(Universe::new(input).total_distance(2))
+            .expect("total distance exceeds usize")
     }
 
     fn part2(input: &str) -> Self::OUTPUT {
-        let mut coords = parsers::part1(input);
-        expand_universe(&mut coords, 1_000_000 - 1);
-        sum_distances(&coords)
+        usize::try_from(Universe::new(input).total_distance(1_000_000))
+            .expect("total distance exceeds usize")
     }
 }
 
-fn sum_distances(coords: &[Coords]) -> usize {
-    coords
-        .iter()
-        .tuple_combinations()
-        .map(|(a, b)| manhattan_distance(*a, *b))
-        .sum()
-}
+pub use universe::Universe;
 
-fn expand_universe(coords: &mut [Coords], expanse_ratio: usize) {
-    let mut max_x = usize::MIN;
-    let mut max_y = usize::MIN;
-    for (x, y) in coords.iter() {
-        max_x = max_x.max(*x);
-        max_y = max_y.max(*y);
+/// Expanded positions from prefix sums of the empty rows and columns,
+/// and distances totalled along each axis over the sorted coordinates
+pub mod universe {
+    use super::*;
+
+    #[derive(Debug, Clone)]
+    pub struct Universe {
+        galaxies: Vec<Coords>,
+        /// Number of empty columns left of each x
+        empty_columns: Vec<usize>,
+        /// Number of empty rows above each y
+        empty_rows: Vec<usize>,
     }
-    for i in (0..=max_x).rev() {
-        if coords.iter().all(|(x, _)| *x != i) {
-            for (x, _) in coords.iter_mut().filter(|(x, _)| *x > i) {
-                *x += expanse_ratio
+    impl Universe {
+        pub fn new(input: &str) -> Self {
+            let galaxies = parsers::part1(input);
+            let width = input.lines().map(str::len).max().unwrap_or(0);
+            let height = input.lines().count();
+            let mut columns = vec![false; width];
+            let mut rows = vec![false; height];
+            for (x, y) in &galaxies {
+                columns[*x] = true;
+                rows[*y] = true;
+            }
+            Self {
+                galaxies,
+                empty_columns: Self::empty_before(&columns),
+                empty_rows: Self::empty_before(&rows),
+            }
+        }
+
+        fn empty_before(occupied: &[bool]) -> Vec<usize> {
+            let mut empty = Vec::with_capacity(occupied.len());
+            let mut count = 0;
+            for occupied in occupied {
+                empty.push(count);
+                count += usize::from(!occupied);
+            }
+            empty
+        }
+
+        /// Galaxies in reading order, before the expansion
+        pub fn galaxies(&self) -> &[Coords] {
+            &self.galaxies
+        }
+
+        /// Position of a galaxy when every empty row and column is replaced by `factor` of them
+        pub fn expanded(&self, galaxy: usize, factor: u128) -> (u128, u128) {
+            let (x, y) = self.galaxies[galaxy];
+            let expand =
+                |position: usize, empty: usize| (position - empty) as u128 + empty as u128 * factor;
+            (
+                expand(x, self.empty_columns[x]),
+                expand(y, self.empty_rows[y]),
+            )
+        }
+
+        /// Manhattan distance between two galaxies, by index in reading order
+        pub fn distance(&self, a: usize, b: usize, factor: u128) -> u128 {
+            let (ax, ay) = self.expanded(a, factor);
+            let (bx, by) = self.expanded(b, factor);
+            ax.abs_diff(bx) + ay.abs_diff(by)
+        }
+
+        /// Sum of the distances between all the pairs of galaxies
+        pub fn total_distance(&self, factor: u128) -> u128 {
+            let (mut xs, mut ys): (Vec<_>, Vec<_>) = (0..self.galaxies.len())
+                .map(|galaxy| self.expanded(galaxy, factor))
+                .unzip();
+            xs.sort_unstable();
+            ys.sort_unstable();
+            Self::sum_differences(&xs) + Self::sum_differences(&ys)
+        }
+
+        /// Each sorted value is the largest of the pairs with all the values before it
+        fn sum_differences(sorted: &[u128]) -> u128 {
+            let mut total = 0;
+            let mut before = 0;
+            for (count, value) in (0..).zip(sorted) {
+                total += value * count - before;
+                before += value;
             }
+            total
         }
     }
-    for i in (0..=max_y).rev() {
-        if coords.iter().all(|(_, y)| *y != i) {
-            for (_, y) in coords.iter_mut().filter(|(_, y)| *y > i) {
-                *y += expanse_ratio
+}
+
+/// First implementation, for benching purposes : expands the coordinates one line at a time,
+/// then goes through all the pairs
+pub mod pairwise {
+    use super::*;
+
+    pub fn total_distance(input: &str, expanse_ratio: usize) -> usize {
+        let mut coords = parsers::part1(input);
+        expand_universe(&mut coords, expanse_ratio);
+        sum_distances(&coords)
+    }
+
+    fn sum_distances(coords: &[Coords]) -> usize {
+        coords
+            .iter()
+            .tuple_combinations()
+            .map(|(a, b)| manhattan_distance(*a, *b))
+            .sum()
+    }
+
+    fn expand_universe(coords: &mut [Coords], expanse_ratio: usize) {
+        let mut max_x = usize::MIN;
+        let mut max_y = usize::MIN;
+        for (x, y) in coords.iter() {
+            max_x = max_x.max(*x);
+            max_y = max_y.max(*y);
+        }
+        for i in (0..=max_x).rev() {
+            if coords.iter().all(|(x, _)| *x != i) {
+                for (x, _) in coords.iter_mut().filter(|(x, _)| *x > i) {
+                    *x += expanse_ratio
+                }
+            }
+        }
+        for i in (0..=max_y).rev() {
+            if coords.iter().all(|(_, y)| *y != i) {
+                for (_, y) in coords.iter_mut().filter(|(_, y)| *y > i) {
+                    *y += expanse_ratio
+                }
             }
         }
     }
@@ -58,7 +157,7 @@ fn manhattan_distance(a: Coords, b: Coords) -> usize {
     a.0.abs_diff(b.0) + a.1.abs_diff(b.1)
 }
 
-type Coords = (usize, usize);
+pub type Coords = (usize, usize);
 
 mod parsers {
     use super::*;
@@ -89,4 +188,40 @@ mod tests {
     fn test_part2() {
         Day::test_part2(0)
     }
+
+    #[test]
+    fn test_universe() {
+        let universe = Universe::new(Day::SAMPLE_PART1);
+        assert_eq!(374, universe.total_distance(2));
+        assert_eq!(1030, universe.total_distance(10));
+        assert_eq!(8410, universe.total_distance(100));
+
+        // Galaxies numbered from 1 in the puzzle
+        assert_eq!(9, universe.distance(4, 8, 2));
+        assert_eq!(15, universe.distance(0, 6, 2));
+        assert_eq!(17, universe.distance(2, 5, 2));
+        assert_eq!(5, universe.distance(7, 8, 2));
+        assert_eq!((3, 0), universe.expanded(0, 1));
+        assert_eq!((4, 0), universe.expanded(0, 2));
+
+        // Distances grow linearly with the factor, beyond u64
+        let factor = 10_u128.pow(30);
+        let (once, twice) = (universe.total_distance(1), universe.total_distance(2));
+        assert_eq!(
+            once + (twice - once) * (factor - 1),
+            universe.total_distance(factor)
+        );
+
+        for (input, ratio) in [
+            (Day::SAMPLE_PART1, 1),
+            (Day::INPUT, 1),
+            (Day::INPUT, 999_999),
+        ] {
+            let expected = pairwise::total_distance(input, ratio) as u128;
+            assert_eq!(
+                expected,
+                Universe::new(input).total_distance(ratio as u128 + 1)
+            );
+        }
+    }
 }
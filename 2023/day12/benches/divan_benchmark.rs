@@ -30,12 +30,40 @@ mod part1 {
 }
 
 mod part2 {
+    use day12::part2_cached;
 
     use super::*;
     #[divan::bench]
     fn cached(bencher: Bencher) {
+        bencher
+            .with_inputs(|| Day::INPUT)
+            .bench_values(|input| part2_cached(input))
+    }
+
+    #[divan::bench]
+    fn dense(bencher: Bencher) {
         bencher
             .with_inputs(|| Day::INPUT)
             .bench_values(|input| Day::part2(input))
     }
 }
+
+mod generated {
+    use day12::{dense, generator};
+
+    use super::*;
+    #[divan::bench]
+    fn part2_short_rows(bencher: Bencher) {
+        bencher
+            .with_inputs(|| generator::input(0, 1000, 20, 0.5))
+            .bench_values(|input| Day::part2(&input))
+    }
+
+    /// Counts beyond usize : the dense solver is benched directly, for its u128 total
+    #[divan::bench]
+    fn part2_long_rows(bencher: Bencher) {
+        bencher
+            .with_inputs(|| generator::input(0, 100, 100, 0.5))
+            .bench_values(|input| dense::total(&input, 5))
+    }
+}
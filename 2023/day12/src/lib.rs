@@ -25,23 +25,31 @@ impl Aoc for Day {
     }
 
     fn part2(input: &str) -> Self::OUTPUT {
-        input
-            .par_lines()
-            .map(|line| {
-                let (_, (springs, pattern)) = parsers::part1(line)
-                    .unwrap_or_else(|e| panic!("Parser failed {e:?} on line {line}"));
+        dense::total(input, 5)
+            .ok()
+            .and_then(|total| usize::try_from(total).ok())
+            .expect("arrangement count exceeds usize")
+    }
+}
 
-                let springs = std::iter::repeat(springs).take(5);
-                let springs = Itertools::intersperse(springs, vec![Spring::Unknown])
-                    .flatten()
-                    .collect_vec();
+/// For benching purposes
+pub fn part2_cached(input: &str) -> usize {
+    input
+        .par_lines()
+        .map(|line| {
+            let (_, (springs, pattern)) = parsers::part1(line)
+                .unwrap_or_else(|e| panic!("Parser failed {e:?} on line {line}"));
 
-                let pattern = std::iter::repeat(pattern).take(5).flatten().collect_vec();
+            let springs = std::iter::repeat(springs).take(5);
+            let springs = Itertools::intersperse(springs, vec![Spring::Unknown])
+                .flatten()
+                .collect_vec();
 
-                cached::permutations(&springs, &pattern, 0, &mut HashMap::new())
-            })
-            .sum()
-    }
+            let pattern = std::iter::repeat(pattern).take(5).flatten().collect_vec();
+
+            cached::permutations(&springs, &pattern, 0, &mut HashMap::new())
+        })
+        .sum()
 }
 
 /// For benching purposes
@@ -158,8 +166,376 @@ mod cached {
     }
 }
 
+/// Bottom-up dynamic programming over a dense table, without any hashing :
+/// `table[i][g]` counts the arrangements of the springs from `i` with the groups from `g`,
+/// when the spring before `i` is not broken.
+/// The same table ranks the arrangements, so that any of them can be built directly from its index
+pub mod dense {
+    use rand::Rng;
+
+    use super::*;
+
+    pub fn total(input: &str, unfold: usize) -> Result<u128, Overflow> {
+        input
+            .par_lines()
+            .map(|line| {
+                let (springs, pattern) = row(line);
+                Ok(Solver::new(&springs, &pattern, unfold)?.count())
+            })
+            .try_reduce(|| 0, |a, b| a.checked_add(b).ok_or(Overflow))
+    }
+
+    pub fn row(line: &str) -> (Vec<Spring>, Vec<u8>) {
+        let (_, row) =
+            parsers::part1(line).unwrap_or_else(|e| panic!("Parser failed {e:?} on line {line}"));
+        row
+    }
+
+    /// More arrangements than a u128 can count
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct Overflow;
+    impl std::fmt::Display for Overflow {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "arrangement count exceeds u128")
+        }
+    }
+
+    #[derive(Debug, Clone)]
+    pub struct Solver {
+        springs: Vec<Spring>,
+        pattern: Vec<u8>,
+        /// Number of springs that may be broken in a row, from each position
+        runs: Vec<usize>,
+        /// None where the count overflows : only positions the first spring can't lead to may keep it
+        table: Vec<Option<u128>>,
+    }
+    impl Solver {
+        /// Repeats the springs `unfold` times, separated by unknown springs, and the pattern as many times.
+        /// Fails when the number of arrangements does not fit in a u128
+        pub fn new(springs: &[Spring], pattern: &[u8], unfold: usize) -> Result<Self, Overflow> {
+            assert!(unfold > 0, "The row must be unfolded at least once");
+            let springs =
+                Itertools::intersperse(std::iter::repeat_n(springs, unfold), &[Spring::Unknown])
+                    .flatten()
+                    .copied()
+                    .collect_vec();
+            let pattern = pattern.repeat(unfold);
+
+            let mut runs = vec![0; springs.len() + 1];
+            for i in (0..springs.len()).rev() {
+                if springs[i] != Spring::Working {
+                    runs[i] = runs[i + 1] + 1;
+                }
+            }
+
+            let mut solver = Self {
+                table: vec![Some(0); (springs.len() + 1) * (pattern.len() + 1)],
+                springs,
+                pattern,
+                runs,
+            };
+            let (length, groups) = (solver.springs.len(), solver.pattern.len());
+            let index = solver.index(length, groups);
+            solver.table[index] = Some(1);
+            for i in (0..length).rev() {
+                for g in 0..=groups {
+                    let working = match solver.springs[i] {
+                        Spring::Broken => Some(0),
+                        _ => solver.table[solver.index(i + 1, g)],
+                    };
+                    let broken = match solver.placement(i, g) {
+                        Some((i, g)) => solver.table[solver.index(i, g)],
+                        None => Some(0),
+                    };
+                    let index = solver.index(i, g);
+                    solver.table[index] = working
+                        .zip(broken)
+                        .and_then(|(working, broken)| working.checked_add(broken));
+                }
+            }
+            match solver.table[0] {
+                Some(_) => Ok(solver),
+                None => Err(Overflow),
+            }
+        }
+
+        fn index(&self, i: usize, g: usize) -> usize {
+            i * (self.pattern.len() + 1) + g
+        }
+
+        /// Only for positions the first spring leads to, which count at most all the arrangements
+        fn get(&self, i: usize, g: usize) -> u128 {
+            self.table[self.index(i, g)].expect("counts reachable from the start fit in u128")
+        }
+
+        /// Arrangements with a working spring at `i`, if it may be working
+        fn working(&self, i: usize, g: usize) -> Option<u128> {
+            (self.springs[i] != Spring::Broken).then(|| self.get(i + 1, g))
+        }
+
+        /// Arrangements with the group `g` starting at `i`, if it fits there :
+        /// the group is followed by the end of the row or a working spring
+        fn broken(&self, i: usize, g: usize) -> Option<u128> {
+            self.placement(i, g).map(|(i, g)| self.get(i, g))
+        }
+
+        /// Position and group after the group `g` starting at `i`, if it fits there
+        fn placement(&self, i: usize, g: usize) -> Option<(usize, usize)> {
+            let length = *self.pattern.get(g)? as usize;
+            if self.runs[i] < length {
+                return None;
+            }
+            match self.springs.get(i + length) {
+                None => Some((i + length, g + 1)),
+                Some(Spring::Broken) => None,
+                Some(_) => Some((i + length + 1, g + 1)),
+            }
+        }
+
+        pub fn springs(&self) -> &[Spring] {
+            &self.springs
+        }
+
+        pub fn pattern(&self) -> &[u8] {
+            &self.pattern
+        }
+
+        pub fn count(&self) -> u128 {
+            self.get(0, 0)
+        }
+
+        /// Arrangement number `index`, in the order of the working springs first
+        pub fn arrangement(&self, mut index: u128) -> Option<Vec<Spring>> {
+            if index >= self.count() {
+                return None;
+            }
+            let mut arrangement = Vec::with_capacity(self.springs.len());
+            let (mut i, mut g) = (0, 0);
+            while i < self.springs.len() {
+                let working = self.working(i, g).unwrap_or(0);
+                if index < working {
+                    arrangement.push(Spring::Working);
+                    i += 1;
+                    continue;
+                }
+                index -= working;
+                let length = self.pattern[g] as usize;
+                arrangement.extend(std::iter::repeat_n(Spring::Broken, length));
+                i += length;
+                g += 1;
+                if i < self.springs.len() {
+                    arrangement.push(Spring::Working);
+                    i += 1;
+                }
+            }
+            Some(arrangement)
+        }
+
+        /// Every arrangement, built lazily one at a time
+        pub fn arrangements(&self) -> impl Iterator<Item = Vec<Spring>> + '_ {
+            (0..self.count()).map(|index| self.arrangement(index).unwrap())
+        }
+
+        /// One of the arrangements, all of them equally likely
+        pub fn sample(&self, rng: &mut impl Rng) -> Option<Vec<Spring>> {
+            let count = self.count();
+            (count > 0).then(|| self.arrangement(rng.gen_range(0..count)).unwrap())
+        }
+
+        /// Arrangements of the springs before each position with the groups before `g`,
+        /// when the spring before the position is not broken : the table built the other way round.
+        /// Counts saturate : those that would overflow lead to no arrangement of the whole row
+        fn forward(&self) -> Vec<u128> {
+            let (length, groups) = (self.springs.len(), self.pattern.len());
+            let mut forward = vec![0_u128; self.table.len()];
+            forward[0] = 1;
+            for i in 0..length {
+                for g in 0..=groups {
+                    let count = forward[self.index(i, g)];
+                    if count == 0 {
+                        continue;
+                    }
+                    if self.springs[i] != Spring::Broken {
+                        let next = self.index(i + 1, g);
+                        forward[next] = forward[next].saturating_add(count);
+                    }
+                    if let Some((i, g)) = self.placement(i, g) {
+                        let next = self.index(i, g);
+                        forward[next] = forward[next].saturating_add(count);
+                    }
+                }
+            }
+            forward
+        }
+
+        /// Number of arrangements with a broken spring at each position
+        pub fn broken_counts(&self) -> Vec<u128> {
+            let forward = self.forward();
+            // Each group placement adds its arrangements to all the springs it covers
+            let mut changes = vec![0_u128; self.springs.len() + 1];
+            for i in 0..self.springs.len() {
+                for g in 0..self.pattern.len() {
+                    let before = forward[self.index(i, g)];
+                    if before == 0 {
+                        continue;
+                    }
+                    let Some(after) = self.broken(i, g) else {
+                        continue;
+                    };
+                    let count = before * after;
+                    let end = i + self.pattern[g] as usize;
+                    changes[i] = changes[i].wrapping_add(count);
+                    changes[end] = changes[end].wrapping_sub(count);
+                }
+            }
+            changes
+                .iter()
+                .take(self.springs.len())
+                .scan(0_u128, |total, change| {
+                    *total = total.wrapping_add(*change);
+                    Some(*total)
+                })
+                .collect()
+        }
+
+        /// The springs, where the unknown ones are replaced when all the arrangements agree.
+        /// None when there is no arrangement at all
+        pub fn deduce(&self) -> Option<Vec<Spring>> {
+            let count = self.count();
+            if count == 0 {
+                return None;
+            }
+            let deduced = self
+                .broken_counts()
+                .into_iter()
+                .zip(&self.springs)
+                .map(|(broken, spring)| match broken {
+                    0 => Spring::Working,
+                    _ if broken == count => Spring::Broken,
+                    _ => *spring,
+                })
+                .collect();
+            Some(deduced)
+        }
+    }
+}
+
+/// Nonograms : the springs of every row and every column follow a pattern.
+/// Each line is deduced with the arrangements solver until nothing changes,
+/// then the first unknown spring is guessed, and the guess is backtracked if it leads to a contradiction
+pub mod nonogram {
+    use super::*;
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct Nonogram {
+        rows: Vec<Vec<u8>>,
+        columns: Vec<Vec<u8>>,
+        cells: Vec<Vec<Spring>>,
+    }
+    impl Nonogram {
+        /// Clues of the rows from top to bottom, and of the columns from left to right
+        pub fn new(rows: Vec<Vec<u8>>, columns: Vec<Vec<u8>>) -> Self {
+            let cells = vec![vec![Spring::Unknown; columns.len()]; rows.len()];
+            Self {
+                rows,
+                columns,
+                cells,
+            }
+        }
+
+        /// The clues of a grid of known springs
+        pub fn from_grid(grid: &[Vec<Spring>]) -> Self {
+            let clues = |line: &mut dyn Iterator<Item = Spring>| {
+                line.dedup_with_count()
+                    .filter(|(_, spring)| *spring == Spring::Broken)
+                    .map(|(count, _)| count as u8)
+                    .collect_vec()
+            };
+            let rows = grid
+                .iter()
+                .map(|row| clues(&mut row.iter().copied()))
+                .collect();
+            let columns = (0..grid[0].len())
+                .map(|x| clues(&mut grid.iter().map(|row| row[x])))
+                .collect();
+            Self::new(rows, columns)
+        }
+
+        pub fn cells(&self) -> &[Vec<Spring>] {
+            &self.cells
+        }
+
+        /// Deduces the lines until nothing changes, false on a contradiction
+        pub fn propagate(&mut self) -> bool {
+            let mut changed = true;
+            while changed {
+                changed = false;
+                for y in 0..self.rows.len() {
+                    // Too many arrangements to count : nothing to deduce on this line yet
+                    let Ok(solver) = dense::Solver::new(&self.cells[y], &self.rows[y], 1) else {
+                        continue;
+                    };
+                    let Some(deduced) = solver.deduce() else {
+                        return false;
+                    };
+                    changed |= deduced != self.cells[y];
+                    self.cells[y] = deduced;
+                }
+                for x in 0..self.columns.len() {
+                    let column = self.cells.iter().map(|row| row[x]).collect_vec();
+                    let Ok(solver) = dense::Solver::new(&column, &self.columns[x], 1) else {
+                        continue;
+                    };
+                    let Some(deduced) = solver.deduce() else {
+                        return false;
+                    };
+                    for (row, spring) in self.cells.iter_mut().zip(deduced) {
+                        changed |= row[x] != spring;
+                        row[x] = spring;
+                    }
+                }
+            }
+            true
+        }
+
+        /// The first solution found, None if the clues contradict each other
+        pub fn solve(&self) -> Option<Vec<Vec<Spring>>> {
+            let mut nonogram = self.clone();
+            if !nonogram.propagate() {
+                return None;
+            }
+            let unknown = nonogram.cells.iter().enumerate().find_map(|(y, row)| {
+                let x = row.iter().position(|spring| *spring == Spring::Unknown)?;
+                Some((x, y))
+            });
+            let Some((x, y)) = unknown else {
+                return Some(nonogram.cells);
+            };
+            [Spring::Broken, Spring::Working]
+                .into_iter()
+                .find_map(|guess| {
+                    let mut guessed = nonogram.clone();
+                    guessed.cells[y][x] = guess;
+                    guessed.solve()
+                })
+        }
+    }
+    impl std::fmt::Display for Nonogram {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            for row in &self.cells {
+                let line = row
+                    .iter()
+                    .map(|spring| spring.as_char())
+                    .collect::<String>();
+                writeln!(f, "{line}")?;
+            }
+            Ok(())
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
-enum Spring {
+pub enum Spring {
     Working,
     Broken,
     Unknown,
@@ -175,6 +551,59 @@ impl TryFrom<char> for Spring {
         }
     }
 }
+impl Spring {
+    pub fn as_char(self) -> char {
+        match self {
+            Spring::Unknown => '?',
+            Spring::Broken => '#',
+            Spring::Working => '.',
+        }
+    }
+}
+
+/// Rows of springs of any length and share of unknown springs, from a seeded random generator
+pub mod generator {
+    use itertools::Itertools;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    /// Every row is built from a random valid arrangement of `length` springs,
+    /// then about `unknown_ratio` of the springs are hidden behind a `?`.
+    /// This guarantees at least one arrangement per row.
+    pub fn input(seed: u64, rows: usize, length: usize, unknown_ratio: f64) -> String {
+        assert!(length > 0, "Rows need at least one spring");
+        let mut rng = StdRng::seed_from_u64(seed);
+        (0..rows)
+            .map(|_| {
+                let mut springs = (0..length).map(|_| rng.gen_bool(0.5)).collect_vec();
+                // The pattern needs at least one group of broken springs
+                let broken = rng.gen_range(0..length);
+                springs[broken] = true;
+
+                let pattern = springs
+                    .iter()
+                    .dedup_with_count()
+                    .filter_map(|(count, broken)| broken.then_some(count))
+                    .join(",");
+                let springs = springs
+                    .into_iter()
+                    .map(|broken| match broken {
+                        _ if rng.gen_bool(unknown_ratio) => '?',
+                        true => '#',
+                        false => '.',
+                    })
+                    .collect::<String>();
+                format!("{springs} {pattern}")
+            })
+            .join("\n")
+    }
+}
+
+#[cfg(feature = "fuzzing")]
+pub fn fuzz_parsers(input: &str) {
+    for line in input.lines() {
+        let _ = parsers::part1(line);
+    }
+}
 
 mod parsers {
     use nom::{
@@ -271,4 +700,218 @@ mod tests {
 
         Day::test_part2(525152)
     }
+
+    #[test]
+    fn test_dense() {
+        for input in [Day::SAMPLE_PART1, Day::INPUT] {
+            assert_eq!(Ok(Day::part1(input) as u128), dense::total(input, 1));
+            assert_eq!(Ok(part2_cached(input) as u128), dense::total(input, 5));
+        }
+
+        // Any unfold factor, beyond u64
+        let (springs, pattern) = dense::row(".??..??...?##. 1,1,3");
+        for unfold in [1, 2, 3, 7] {
+            let unfolded = std::iter::repeat_n(".??..??...?##.", unfold).join("?");
+            let groups = std::iter::repeat_n("1,1,3", unfold).join(",");
+            let line = format!("{unfolded} {groups}");
+            let expected = dense::Solver::new(&springs, &pattern, unfold)
+                .unwrap()
+                .count();
+            assert_eq!(Day::part1(&line) as u128, expected, "{unfold}");
+        }
+        let solver = dense::Solver::new(&springs, &pattern, 30).unwrap();
+        assert_eq!(4 * 8_u128.pow(29), solver.count());
+
+        // 4 * 8^42 is 2^128, one more than u128::MAX
+        let solver = dense::Solver::new(&springs, &pattern, 42).unwrap();
+        assert_eq!(4 * 8_u128.pow(41), solver.count());
+        assert!(solver.arrangement(solver.count() - 1).is_some());
+        assert!(solver.deduce().is_some());
+        let overflow = dense::Solver::new(&springs, &pattern, 43);
+        assert_eq!(Some(dense::Overflow), overflow.err());
+        let line = std::iter::repeat_n(".??..??...?##. 1,1,3", 7).join("\n");
+        assert!(dense::total(&line, 42).is_ok());
+        let line = std::iter::repeat_n(".??..??...?##. 1,1,3", 8).join("\n");
+        assert_eq!(Err(dense::Overflow), dense::total(&line, 42));
+    }
+
+    /// Arrangements only contain known springs, which follow the row and the pattern
+    fn is_arrangement(arrangement: &[Spring], springs: &[Spring], pattern: &[u8]) -> bool {
+        let groups = arrangement
+            .iter()
+            .dedup_with_count()
+            .filter_map(|(count, spring)| (*spring == Spring::Broken).then_some(count as u8))
+            .collect_vec();
+        arrangement.len() == springs.len()
+            && groups == pattern
+            && arrangement
+                .iter()
+                .zip(springs)
+                .all(|(a, s)| *a != Spring::Unknown && (*s == Spring::Unknown || a == s))
+    }
+
+    #[test]
+    fn test_arrangements() {
+        use rand::{rngs::StdRng, SeedableRng};
+
+        let (springs, pattern) = dense::row("?###???????? 3,2,1");
+        let solver = dense::Solver::new(&springs, &pattern, 1).unwrap();
+        let arrangements = solver.arrangements().collect_vec();
+        assert_eq!(10, arrangements.len());
+        assert_eq!(10, arrangements.iter().unique().count());
+        assert!(arrangements
+            .iter()
+            .all(|a| is_arrangement(a, &springs, &pattern)));
+        let first = arrangements[0]
+            .iter()
+            .map(|s| s.as_char())
+            .collect::<String>();
+        assert_eq!(".###....##.#", first);
+        assert_eq!(None, solver.arrangement(10));
+
+        let mut rng = StdRng::seed_from_u64(12);
+        let mut counts = HashMap::new();
+        for _ in 0..10_000 {
+            let sample = solver.sample(&mut rng).unwrap();
+            *counts.entry(sample).or_insert(0) += 1;
+        }
+        assert_eq!(10, counts.len());
+        assert!(counts.values().all(|count| (800..1200).contains(count)));
+
+        // Unfolded rows, and rows without arrangements
+        let solver = dense::Solver::new(&springs, &pattern, 3).unwrap();
+        let sample = solver.sample(&mut rng).unwrap();
+        assert!(is_arrangement(&sample, solver.springs(), solver.pattern()));
+        let (springs, pattern) = dense::row("#.# 2");
+        assert_eq!(
+            None,
+            dense::Solver::new(&springs, &pattern, 1)
+                .unwrap()
+                .sample(&mut rng)
+        );
+    }
+
+    #[test]
+    fn test_deduce() {
+        let (springs, pattern) = dense::row("?###???????? 3,2,1");
+        let solver = dense::Solver::new(&springs, &pattern, 1).unwrap();
+        let deduced = solver.deduce().unwrap();
+        let deduced = deduced.iter().map(|s| s.as_char()).collect::<String>();
+        assert_eq!(".###.???????", deduced);
+
+        let (springs, pattern) = dense::row("???.### 1,1,3");
+        let solver = dense::Solver::new(&springs, &pattern, 2).unwrap();
+        let deduced = solver.deduce().unwrap();
+        let deduced = deduced.iter().map(|s| s.as_char()).collect::<String>();
+        assert_eq!("#.#.###.#.#.###", deduced);
+
+        let (springs, pattern) = dense::row("#.# 2");
+        assert_eq!(
+            None,
+            dense::Solver::new(&springs, &pattern, 1).unwrap().deduce()
+        );
+    }
+
+    #[test]
+    fn test_nonogram() {
+        use rand::{rngs::StdRng, Rng, SeedableRng};
+
+        let clues = |clues: &[&[u8]]| clues.iter().map(|c| c.to_vec()).collect_vec();
+        let diamond = nonogram::Nonogram::new(
+            clues(&[&[1], &[3], &[5], &[3], &[1]]),
+            clues(&[&[1], &[3], &[5], &[3], &[1]]),
+        );
+        let mut propagated = diamond.clone();
+        assert!(propagated.propagate());
+        assert_eq!(
+            "..#..\n.###.\n#####\n.###.\n..#..\n",
+            propagated.to_string()
+        );
+
+        // Two diagonals fit the clues : only the guesses can tell them apart
+        let diagonal = nonogram::Nonogram::new(clues(&[&[1], &[1]]), clues(&[&[1], &[1]]));
+        let mut propagated = diagonal.clone();
+        assert!(propagated.propagate());
+        assert_eq!("??\n??\n", propagated.to_string());
+        let solution = diagonal.solve().unwrap();
+        assert_eq!(diagonal, nonogram::Nonogram::from_grid(&solution));
+
+        let impossible = nonogram::Nonogram::new(clues(&[&[2], &[]]), clues(&[&[1], &[]]));
+        assert_eq!(None, impossible.solve());
+
+        let mut rng = StdRng::seed_from_u64(48);
+        for _ in 0..20 {
+            let grid = (0..8)
+                .map(|_| {
+                    (0..10)
+                        .map(|_| match rng.gen_bool(0.5) {
+                            true => Spring::Broken,
+                            false => Spring::Working,
+                        })
+                        .collect_vec()
+                })
+                .collect_vec();
+            let nonogram = nonogram::Nonogram::from_grid(&grid);
+            let solution = nonogram.solve().unwrap();
+            assert_eq!(nonogram, nonogram::Nonogram::from_grid(&solution));
+        }
+    }
+
+    #[test]
+    fn test_generator() {
+        let input = generator::input(12, 50, 12, 0.5);
+        assert_eq!(input, generator::input(12, 50, 12, 0.5));
+        assert_eq!(part1_brute_force(&input), Day::part1(&input));
+        assert!(input.lines().all(|line| Day::part1(line) >= 1));
+    }
+
+    mod proptests {
+        use super::*;
+        use proptest::prelude::*;
+
+        /// Generates a single line of springs and its pattern.
+        /// Lines are kept short because the brute force is exponential on the number of unknown springs.
+        fn line() -> impl Strategy<Value = String> {
+            (
+                prop::collection::vec(prop::sample::select(vec!['?', '#', '.']), 1..=14),
+                prop::collection::vec(1..=4_u8, 1..=4),
+            )
+                .prop_map(|(springs, pattern)| {
+                    format!(
+                        "{} {}",
+                        springs.iter().collect::<String>(),
+                        pattern.iter().join(",")
+                    )
+                })
+        }
+
+        proptest! {
+            #[test]
+            fn part1_cached_agrees_with_brute_force(lines in prop::collection::vec(line(), 1..=10)) {
+                let input = lines.join("\n");
+                prop_assert_eq!(part1_brute_force(&input), Day::part1(&input));
+            }
+
+            #[test]
+            fn dense_arrangements_agree_with_brute_force(line in line()) {
+                let (springs, pattern) = dense::row(&line);
+                let solver = dense::Solver::new(&springs, &pattern, 1).unwrap();
+                prop_assert_eq!(part1_brute_force(&line) as u128, solver.count());
+                let arrangements = solver.arrangements().collect_vec();
+                prop_assert_eq!(arrangements.len(), arrangements.iter().unique().count());
+                prop_assert!(arrangements.iter().all(|a| is_arrangement(a, &springs, &pattern)));
+
+                // Deduced springs are the ones all the arrangements agree on
+                let deduced = solver.deduce();
+                prop_assert_eq!(arrangements.is_empty(), deduced.is_none());
+                if let Some(deduced) = deduced {
+                    for (i, spring) in deduced.iter().enumerate() {
+                        let first = arrangements[0][i];
+                        let agree = arrangements.iter().all(|a| a[i] == first);
+                        prop_assert_eq!(*spring, if agree { first } else { Spring::Unknown });
+                    }
+                }
+            }
+        }
+    }
 }
@@ -1,5 +1,4 @@
 use aoc::Aoc;
-use itertools::Itertools;
 use rayon::prelude::*;
 
 pub struct Day;
@@ -12,26 +11,16 @@ impl Aoc for Day {
     const SAMPLE_PART2: &'static str = include_str!("../inputs/sample2.txt");
 
     fn part1(input: &str) -> Self::OUTPUT {
-        input
-            .split("\r\n\r\n")
+        aoc::input::blocks(input)
             .par_bridge()
-            .map(|str| {
-                let (_, grid) = parsers::part1(str)
-                    .unwrap_or_else(|e| panic!("Parser failed {e:?} on input {str}"));
-                Symmetry::find_symmetry_part1(&grid).to_number()
-            })
+            .map(|str| Pattern::new(str).summary(0))
             .sum()
     }
 
     fn part2(input: &str) -> Self::OUTPUT {
-        input
-            .split("\r\n\r\n")
+        aoc::input::blocks(input)
             .par_bridge()
-            .map(|str| {
-                let (_, grid) = parsers::part1(str)
-                    .unwrap_or_else(|e| panic!("Parser failed {e:?} on input {str}"));
-                Symmetry::find_symmetry_part2(&grid).to_number()
-            })
+            .map(|str| Pattern::new(str).summary(1))
             .sum()
     }
 }
@@ -40,120 +29,222 @@ impl Aoc for Day {
 pub mod single_thread {
     use super::*;
     pub fn part1(input: &str) -> u32 {
-        input
-            .split("\r\n\r\n")
-            .map(|str| {
-                let (_, grid) = parsers::part1(str)
-                    .unwrap_or_else(|e| panic!("Parser failed {e:?} on input {str}"));
-                Symmetry::find_symmetry_part1(&grid).to_number()
-            })
+        aoc::input::blocks(input)
+            .map(|str| Pattern::new(str).summary(0))
             .sum()
     }
 
     pub fn part2(input: &str) -> u32 {
-        input
-            .split("\r\n\r\n")
-            .map(|str| {
-                let (_, grid) = parsers::part1(str)
-                    .unwrap_or_else(|e| panic!("Parser failed {e:?} on input {str}"));
-                Symmetry::find_symmetry_part2(&grid).to_number()
-            })
+        aoc::input::blocks(input)
+            .map(|str| Pattern::new(str).summary(1))
             .sum()
     }
 }
 
-/// Caution with the puzzle text :
-/// - A Horizontal line of reflection == Vertical Symmetry  
-/// - A Vertical line of reflection   ==  Horizontal Symmetry
-#[derive(Debug)]
-enum Symmetry {
-    Vertical(usize),
-    Horizontal(usize),
-}
-impl Symmetry {
-    fn to_number(self) -> u32 {
-        match self {
-            Self::Horizontal(n) => n as u32,
-            Self::Vertical(n) => 100 * n as u32,
+pub use mirrors::{Mirror, Pattern, Reflection};
+
+/// Rows and columns as bitmasks : the number of differences between two of them is the popcount of their XOR
+pub mod mirrors {
+    use super::*;
+
+    /// Caution with the puzzle text : the reflection lines are between rows or between columns
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum Reflection {
+        /// Line below this number of rows
+        Row(usize),
+        /// Line right of this number of columns
+        Column(usize),
+    }
+    impl Reflection {
+        /// Number of columns left of the line, or 100 times the number of rows above it
+        pub fn summary(self) -> u32 {
+            match self {
+                Self::Row(n) => 100 * n as u32,
+                Self::Column(n) => n as u32,
+            }
         }
     }
 
-    fn find_symmetry_part1(input: &Vec<Vec<Tile>>) -> Self {
-        let height = input.len();
-        let width = input[0].len();
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct Mirror {
+        pub reflection: Reflection,
+        /// Cells (x, y) which differ from their reflection, with that reflection :
+        /// fixing either one of them fixes the smudge
+        pub smudges: Vec<((usize, usize), (usize, usize))>,
+    }
 
-        // Vertical symmetry
-        if let Some(y) = (0..height - 1).position(|y| {
-            (0..width).all(|x| {
-                let above_mirror = (0..=y).rev().map(|i| &input[i][x]);
-                let below_mirror = (y + 1..height).map(|i| &input[i][x]);
-                above_mirror.zip(below_mirror).all(|(a, b)| a == b)
-            })
-        }) {
-            return Self::Vertical(y + 1);
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct Pattern {
+        width: usize,
+        height: usize,
+        /// Bit x of row y is set for a mirror '#'
+        rows: Vec<u64>,
+        /// Bit y of column x is set for a mirror '#'
+        columns: Vec<u64>,
+    }
+    impl Pattern {
+        pub fn new(input: &str) -> Self {
+            let (_, tiles) = parsers::part1(input)
+                .unwrap_or_else(|e| panic!("Parser failed {e:?} on input {input}"));
+            Self::from_tiles(&tiles)
         }
 
-        // Horizontal symmetry
-        if let Some(x) = (0..width - 1).position(|x| {
-            (0..height).all(|y| {
-                let left_of_mirror = (0..=x).rev().map(|i| &input[y][i]);
-                let right_of_mirror = (x + 1..width).map(|i| &input[y][i]);
-                left_of_mirror.zip(right_of_mirror).all(|(l, r)| l == r)
+        pub(crate) fn from_tiles(tiles: &[Vec<Tile>]) -> Self {
+            let (width, height) = (tiles[0].len(), tiles.len());
+            assert!(
+                width <= 64 && height <= 64,
+                "Patterns are limited to 64x64 tiles"
+            );
+            let mut rows = vec![0; height];
+            let mut columns = vec![0; width];
+            for (y, line) in tiles.iter().enumerate() {
+                for (x, tile) in line.iter().enumerate() {
+                    if *tile == Tile::Mirror {
+                        rows[y] |= 1 << x;
+                        columns[x] |= 1 << y;
+                    }
+                }
+            }
+            Self {
+                width,
+                height,
+                rows,
+                columns,
+            }
+        }
+
+        /// Every reflection line with exactly `smudges` differences, between rows first, then between columns
+        pub fn reflections(&self, smudges: u32) -> Vec<Mirror> {
+            let rows = Self::lines(&self.rows, smudges).map(|(n, diffs)| Mirror {
+                reflection: Reflection::Row(n),
+                smudges: diffs
+                    .into_iter()
+                    .map(|(a, b, x)| ((x, a), (x, b)))
+                    .collect(),
+            });
+            let columns = Self::lines(&self.columns, smudges).map(|(n, diffs)| Mirror {
+                reflection: Reflection::Column(n),
+                smudges: diffs
+                    .into_iter()
+                    .map(|(a, b, y)| ((a, y), (b, y)))
+                    .collect(),
+            });
+            rows.chain(columns).collect()
+        }
+
+        /// Sum of the differences of the lines on both sides of each mirror position :
+        /// mirror positions with the right count, and the differing (line, reflected line, bit)
+        fn lines(
+            lines: &[u64],
+            smudges: u32,
+        ) -> impl Iterator<Item = (usize, Vec<(usize, usize, usize)>)> + '_ {
+            (1..lines.len()).filter_map(move |n| {
+                let pairs = (0..n).rev().zip(n..lines.len());
+                let differences = pairs
+                    .clone()
+                    .map(|(a, b)| (lines[a] ^ lines[b]).count_ones())
+                    .try_fold(0, |total, count| {
+                        let total = total + count;
+                        (total <= smudges).then_some(total)
+                    });
+                if differences != Some(smudges) {
+                    return None;
+                }
+                let mut diffs = Vec::new();
+                for (a, b) in pairs {
+                    let mut xor = lines[a] ^ lines[b];
+                    while xor != 0 {
+                        diffs.push((a, b, xor.trailing_zeros() as usize));
+                        xor &= xor - 1;
+                    }
+                }
+                Some((n, diffs))
             })
-        }) {
-            return Self::Horizontal(x + 1);
         }
 
-        panic!("Symmetry not found : \n{}", vec2d_to_string(input),)
+        /// Summary of the first reflection line with exactly `smudges` differences
+        pub fn summary(&self, smudges: u32) -> u32 {
+            match self.reflections(smudges).first() {
+                Some(mirror) => mirror.reflection.summary(),
+                None => panic!("Symmetry not found : \n{self}"),
+            }
+        }
+    }
+    impl std::fmt::Display for Pattern {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            for row in &self.rows {
+                let line = (0..self.width)
+                    .map(|x| if row & (1 << x) != 0 { '#' } else { '.' })
+                    .collect::<String>();
+                writeln!(f, "{line}")?;
+            }
+            Ok(())
+        }
+    }
+}
+
+/// The original search, comparing the tiles one by one on both sides of every line.
+/// Only used to check the bitmasks
+#[cfg(test)]
+mod naive {
+    use super::*;
+
+    pub fn part1(input: &str) -> u32 {
+        aoc::input::blocks(input).map(|str| summary(str, 0)).sum()
     }
 
-    fn find_symmetry_part2(input: &Vec<Vec<Tile>>) -> Self {
+    pub fn part2(input: &str) -> u32 {
+        aoc::input::blocks(input).map(|str| summary(str, 1)).sum()
+    }
+
+    fn summary(input: &str, smudges: usize) -> u32 {
+        let (_, tiles) = parsers::part1(input)
+            .unwrap_or_else(|e| panic!("Parser failed {e:?} on input {input}"));
+        match reflections(&tiles, smudges).first() {
+            Some(reflection) => reflection.summary(),
+            None => panic!("Symmetry not found : \n{input}"),
+        }
+    }
+
+    /// Every line with exactly `smudges` differing tiles, between rows first, then between columns
+    pub fn reflections(input: &[Vec<Tile>], smudges: usize) -> Vec<Reflection> {
         let height = input.len();
         let width = input[0].len();
 
-        // Vertical symmetry
-        if let Some(y) = (0..height - 1).position(|y| {
+        let rows = (1..height).filter(|&y| {
             (0..width)
                 .map(|x| {
-                    let above_mirror = (0..=y).rev().map(|i| &input[i][x]);
-                    let below_mirror = (y + 1..height).map(|i| &input[i][x]);
+                    let above_mirror = (0..y).rev().map(|i| &input[i][x]);
+                    let below_mirror = (y..height).map(|i| &input[i][x]);
                     above_mirror
                         .zip(below_mirror)
                         .filter(|(a, b)| a != b)
                         .count()
                 })
                 .sum::<usize>()
-                == 1
-        }) {
-            return Self::Vertical(y + 1);
-        }
+                == smudges
+        });
 
-        // Horizontal symmetry
-        if let Some(x) = (0..width - 1).position(|x| {
+        let columns = (1..width).filter(|&x| {
             (0..height)
                 .map(|y| {
-                    let left_of_mirror = (0..=x).rev().map(|i| &input[y][i]);
-                    let right_of_mirror = (x + 1..width).map(|i| &input[y][i]);
+                    let left_of_mirror = (0..x).rev().map(|i| &input[y][i]);
+                    let right_of_mirror = (x..width).map(|i| &input[y][i]);
                     left_of_mirror
                         .zip(right_of_mirror)
                         .filter(|(l, r)| l != r)
                         .count()
                 })
                 .sum::<usize>()
-                == 1
-        }) {
-            return Self::Horizontal(x + 1);
-        }
+                == smudges
+        });
 
-        panic!("Symmetry not found : \n{}", vec2d_to_string(input),)
+        rows.map(Reflection::Row)
+            .chain(columns.map(Reflection::Column))
+            .collect()
     }
 }
 
-/// Prints the grid for debugging
-fn vec2d_to_string(input: &Vec<Vec<Tile>>) -> String {
-    input.iter().map(|line| format!("{line:?}")).join("\n")
-}
-
 #[derive(Debug, PartialEq)]
 enum Tile {
     Ground,
@@ -170,6 +261,14 @@ impl TryFrom<char> for Tile {
     }
 }
 
+/// Each pattern after the blocks split, like the solvers
+#[cfg(feature = "fuzzing")]
+pub fn fuzz_parsers(input: &str) {
+    for pattern in aoc::input::blocks(input) {
+        let _ = parsers::part1(pattern);
+    }
+}
+
 mod parsers {
     use nom::{
         character::complete::{anychar, line_ending},
@@ -235,4 +334,139 @@ mod tests {
 
         Day::test_part2(400)
     }
+
+    #[test]
+    fn test_mirrors() {
+        let first = Pattern::new(include_str!("../inputs/sample1.1.txt"));
+        let second = Pattern::new(include_str!("../inputs/sample1.2.txt"));
+        let mirror = |reflection, smudges| Mirror {
+            reflection,
+            smudges,
+        };
+        assert_eq!(
+            first.reflections(0),
+            [mirror(Reflection::Column(5), vec![])]
+        );
+        assert_eq!(second.reflections(0), [mirror(Reflection::Row(4), vec![])]);
+        assert_eq!(
+            first.reflections(1),
+            [mirror(Reflection::Row(3), vec![((0, 0), (0, 5))])]
+        );
+        assert_eq!(
+            second.reflections(1),
+            [mirror(Reflection::Row(1), vec![((4, 0), (4, 1))])]
+        );
+
+        // All the reflection lines, with any number of smudges
+        let pattern = Pattern::new("#..#\n#..#\n....");
+        assert_eq!(
+            pattern.reflections(0),
+            [
+                mirror(Reflection::Row(1), vec![]),
+                mirror(Reflection::Column(2), vec![])
+            ]
+        );
+        assert_eq!(
+            pattern.reflections(2),
+            [
+                mirror(Reflection::Row(2), vec![((0, 1), (0, 2)), ((3, 1), (3, 2))]),
+                mirror(
+                    Reflection::Column(1),
+                    vec![((0, 0), (1, 0)), ((0, 1), (1, 1))]
+                ),
+                mirror(
+                    Reflection::Column(3),
+                    vec![((2, 0), (3, 0)), ((2, 1), (3, 1))]
+                ),
+            ]
+        );
+    }
+
+    mod proptests {
+        use super::*;
+        use itertools::Itertools;
+        use proptest::prelude::*;
+
+        /// A random pattern, forced to be symmetric around a random line.
+        /// With `smudge`, one tile of the mirrored half is flipped,
+        /// so there is a line with exactly one difference (part 2)
+        fn pattern(smudge: bool) -> impl Strategy<Value = String> {
+            (2..=15_usize, 2..=15_usize, prop::bool::ANY)
+                .prop_flat_map(|(width, height, rows)| {
+                    let size = if rows { height } else { width };
+                    let cells = prop::collection::vec(
+                        prop::collection::vec(prop::bool::ANY, width),
+                        height,
+                    );
+                    (
+                        Just(rows),
+                        1..size,
+                        cells,
+                        any::<prop::sample::Index>(),
+                        any::<prop::sample::Index>(),
+                    )
+                })
+                .prop_map(move |(rows, mirror, cells, smudge_offset, smudge_cross)| {
+                    let mut cells = if rows { cells } else { transpose(&cells) };
+                    let reflected = mirror.min(cells.len() - mirror);
+                    for offset in 0..reflected {
+                        cells[mirror + offset] = cells[mirror - 1 - offset].clone();
+                    }
+                    if smudge {
+                        let row = mirror + smudge_offset.index(reflected);
+                        let column = smudge_cross.index(cells[row].len());
+                        cells[row][column] = !cells[row][column];
+                    }
+                    let cells = if rows { cells } else { transpose(&cells) };
+                    cells
+                        .iter()
+                        .map(|line| {
+                            line.iter()
+                                .map(|b| if *b { '#' } else { '.' })
+                                .collect::<String>()
+                        })
+                        .join("\r\n")
+                })
+        }
+
+        fn transpose(cells: &[Vec<bool>]) -> Vec<Vec<bool>> {
+            (0..cells[0].len())
+                .map(|x| cells.iter().map(|line| line[x]).collect())
+                .collect()
+        }
+
+        proptest! {
+            #[test]
+            fn part1_agrees_with_naive(patterns in prop::collection::vec(pattern(false), 1..=8)) {
+                let input = patterns.join("\r\n\r\n");
+                let expected = naive::part1(&input);
+                prop_assert_eq!(expected, Day::part1(&input));
+                prop_assert_eq!(expected, single_thread::part1(&input));
+            }
+
+            #[test]
+            fn part2_agrees_with_naive(patterns in prop::collection::vec(pattern(true), 1..=8)) {
+                let input = patterns.join("\r\n\r\n");
+                let expected = naive::part2(&input);
+                prop_assert_eq!(expected, Day::part2(&input));
+                prop_assert_eq!(expected, single_thread::part2(&input));
+            }
+
+            #[test]
+            fn reflections_agree_with_naive(input in pattern(false), smudged in pattern(true)) {
+                for input in [input, smudged] {
+                    let (_, tiles) = parsers::part1(&input).unwrap();
+                    let pattern = Pattern::from_tiles(&tiles);
+                    for smudges in 0..=1 {
+                        let mirrors = pattern.reflections(smudges);
+                        let reflections = mirrors.iter().map(|m| m.reflection).collect_vec();
+                        prop_assert_eq!(naive::reflections(&tiles, smudges as usize), reflections);
+                        for mirror in mirrors {
+                            prop_assert_eq!(mirror.smudges.len(), smudges as usize);
+                        }
+                    }
+                }
+            }
+        }
+    }
 }
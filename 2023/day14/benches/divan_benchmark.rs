@@ -65,10 +65,20 @@ mod components {
             .with_inputs(|| grid.clone())
             .bench_values(|mut grid| day14::move_east(&mut grid))
     }
+
+    #[divan::bench]
+    fn bitboard_cycle(bencher: Bencher) {
+        let input = Day::INPUT;
+        let (_, grid) = parsers::grid(input).unwrap();
+        let platform = day14::bitboard::Platform::new(&grid);
+        bencher
+            .with_inputs(|| platform.clone())
+            .bench_values(|mut platform| platform.cycle())
+    }
 }
 
 mod part2 {
-    use day14::dual_thread;
+    use day14::{cell_by_cell, dual_thread};
 
     use super::*;
     #[divan::bench]
@@ -84,4 +94,11 @@ mod part2 {
             .with_inputs(|| Day::INPUT)
             .bench_values(|input| dual_thread::part2(input))
     }
+
+    #[divan::bench]
+    fn cell_by_cell(bencher: Bencher) {
+        bencher
+            .with_inputs(|| Day::INPUT)
+            .bench_values(|input| cell_by_cell::part2(input))
+    }
 }
@@ -19,6 +19,15 @@ impl Aoc for Day {
     }
 
     fn part2(input: &str) -> Self::OUTPUT {
+        bitboard::part2(input)
+    }
+}
+
+/// First implementation, with the whole grids in the history
+pub mod cell_by_cell {
+    use super::*;
+
+    pub fn part2(input: &str) -> usize {
         let (_, mut grid) = parsers::grid(input).unwrap_or_else(|e| panic!("Parser failed {e:?}"));
 
         let mut cycle_history = Vec::new();
@@ -38,7 +47,7 @@ impl Aoc for Day {
     }
 }
 
-type Grid = Vec<Vec<Option<Rock>>>;
+pub type Grid = Vec<Vec<Option<Rock>>>;
 #[allow(dead_code)]
 fn debug_grid(grid: &Grid) -> String {
     grid.iter()
@@ -162,6 +171,423 @@ impl Rock {
     }
 }
 
+/// Round rocks as bitsets, along the rows or the columns depending on the last tilt.
+/// The square rocks never move : the free segments between them are computed once per line,
+/// and a tilt only counts the round rocks in each segment and packs them at one end
+pub mod bitboard {
+    use std::{collections::HashMap, ops::Range};
+
+    use super::*;
+
+    pub fn part2(input: &str) -> usize {
+        let (_, grid) = parsers::grid(input).unwrap_or_else(|e| panic!("Parser failed {e:?}"));
+        let sequence = [Tilt::North, Tilt::West, Tilt::South, Tilt::East];
+        Platform::new(&grid)
+            .spin(&sequence, Tilt::North)
+            .load_after(1_000_000_000)
+    }
+
+    /// Load on `side` after any number of cycles of the tilt `sequence`, e.g. `"NWSE"`
+    pub fn load_after(
+        input: &str,
+        sequence: &str,
+        cycles: usize,
+        side: Tilt,
+    ) -> Result<usize, InvalidTilt> {
+        let (_, grid) = parsers::grid(input).unwrap_or_else(|e| panic!("Parser failed {e:?}"));
+        let sequence = Tilt::sequence(sequence)?;
+        Ok(Platform::new(&grid)
+            .spin(&sequence, side)
+            .load_after(cycles))
+    }
+
+    /// Loads until the platform comes back to a previous state, which it then repeats forever
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct Dynamics {
+        /// Load after 0, 1, 2... cycles, up to the end of the first loop
+        pub loads: Vec<usize>,
+        /// Cycles before entering the loop
+        pub loop_start: usize,
+        pub loop_length: usize,
+    }
+    impl Dynamics {
+        pub fn load_after(&self, cycles: usize) -> usize {
+            let index = match cycles < self.loop_start {
+                true => cycles,
+                false => self.loop_start + (cycles - self.loop_start) % self.loop_length,
+            };
+            self.loads[index]
+        }
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum Tilt {
+        North,
+        South,
+        West,
+        East,
+    }
+    impl TryFrom<char> for Tilt {
+        type Error = InvalidTilt;
+
+        fn try_from(c: char) -> Result<Self, Self::Error> {
+            match c {
+                'N' => Ok(Tilt::North),
+                'S' => Ok(Tilt::South),
+                'W' => Ok(Tilt::West),
+                'E' => Ok(Tilt::East),
+                _ => Err(InvalidTilt(c)),
+            }
+        }
+    }
+    impl Tilt {
+        /// One tilt per letter among `NSWE`
+        pub fn sequence(sequence: &str) -> Result<Vec<Self>, InvalidTilt> {
+            sequence.chars().map(Tilt::try_from).collect()
+        }
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct InvalidTilt(pub char);
+    impl std::fmt::Display for InvalidTilt {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "invalid tilt '{}', expected one of N, S, W, E", self.0)
+        }
+    }
+
+    /// Bits of several lines of the same length, each line starting on a new word
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    struct Bits {
+        stride: usize,
+        words: Vec<u64>,
+    }
+    impl Bits {
+        fn new(lines: usize, length: usize) -> Self {
+            let stride = length.div_ceil(64);
+            Self {
+                stride,
+                words: vec![0; lines * stride],
+            }
+        }
+
+        fn line(&self, line: usize) -> &[u64] {
+            &self.words[line * self.stride..(line + 1) * self.stride]
+        }
+
+        fn line_mut(&mut self, line: usize) -> &mut [u64] {
+            &mut self.words[line * self.stride..(line + 1) * self.stride]
+        }
+
+        fn set(&mut self, line: usize, index: usize) {
+            self.line_mut(line)[index / 64] |= 1 << (index % 64);
+        }
+
+        /// Positions of the set bits of a line
+        fn ones(&self, line: usize) -> impl Iterator<Item = usize> + '_ {
+            self.line(line).iter().enumerate().flat_map(|(w, word)| {
+                let mut word = *word;
+                std::iter::from_fn(move || {
+                    (word != 0).then(|| {
+                        let bit = word.trailing_zeros() as usize;
+                        word &= word - 1;
+                        w * 64 + bit
+                    })
+                })
+            })
+        }
+
+        /// Word indexes and masks covering the range
+        fn masks(range: Range<usize>) -> impl Iterator<Item = (usize, u64)> {
+            let words = range.start / 64..range.end.div_ceil(64);
+            words.map(move |w| {
+                let low = range.start.max(w * 64) - w * 64;
+                let high = range.end.min(w * 64 + 64) - w * 64;
+                (w, mask(low, high))
+            })
+        }
+
+        /// Moves the set bits of the range to one of its ends
+        fn pack(line: &mut [u64], range: Range<usize>, towards_start: bool) {
+            let word = range.start / 64;
+            // Most segments fit in a single word
+            if (range.end - 1) / 64 == word {
+                let (low, high) = (range.start - word * 64, range.end - word * 64);
+                let count = (line[word] & mask(low, high)).count_ones() as usize;
+                let packed = match towards_start {
+                    true => mask(low, low + count),
+                    false => mask(high - count, high),
+                };
+                line[word] = (line[word] & !mask(low, high)) | packed;
+                return;
+            }
+            let count = Self::count(line, range.clone());
+            Self::clear(line, range.clone());
+            match towards_start {
+                true => Self::fill(line, range.start..range.start + count),
+                false => Self::fill(line, range.end - count..range.end),
+            }
+        }
+
+        fn count(line: &[u64], range: Range<usize>) -> usize {
+            Self::masks(range)
+                .map(|(w, mask)| (line[w] & mask).count_ones() as usize)
+                .sum()
+        }
+
+        fn clear(line: &mut [u64], range: Range<usize>) {
+            for (w, mask) in Self::masks(range) {
+                line[w] &= !mask;
+            }
+        }
+
+        fn fill(line: &mut [u64], range: Range<usize>) {
+            for (w, mask) in Self::masks(range) {
+                line[w] |= mask;
+            }
+        }
+
+        /// Swaps the lines and the positions along them, 64 by 64 bits blocks at a time
+        fn transpose(&self, lines: usize, length: usize) -> Self {
+            let mut transposed = Self::new(length, lines);
+            let mut block = [0; 64];
+            for line_block in 0..lines.div_ceil(64) {
+                for word in 0..self.stride {
+                    for (i, row) in block.iter_mut().enumerate() {
+                        let line = line_block * 64 + i;
+                        *row = if line < lines {
+                            self.line(line)[word]
+                        } else {
+                            0
+                        };
+                    }
+                    transpose64(&mut block);
+                    for (i, row) in block.iter().enumerate() {
+                        let line = word * 64 + i;
+                        if line < length {
+                            transposed.line_mut(line)[line_block] = *row;
+                        }
+                    }
+                }
+            }
+            transposed
+        }
+    }
+
+    /// Bits `low..high` of a word
+    fn mask(low: usize, high: usize) -> u64 {
+        match high - low {
+            0 => 0,
+            64 => u64::MAX,
+            len => ((1 << len) - 1) << low,
+        }
+    }
+
+    /// Bit `j` of word `i` becomes bit `i` of word `j`, swapping ever smaller blocks
+    fn transpose64(block: &mut [u64; 64]) {
+        let mut width = 32;
+        let mut mask: u64 = 0x0000_0000_ffff_ffff;
+        while width != 0 {
+            let mut k = 0;
+            while k < 64 {
+                let swap = ((block[k] >> width) ^ block[k + width]) & mask;
+                block[k] ^= swap << width;
+                block[k + width] ^= swap;
+                k = (k + width + 1) & !width;
+            }
+            width >>= 1;
+            mask ^= mask << width;
+        }
+    }
+
+    /// Free positions of a line between the square rocks
+    fn segments(squares: &Bits, lines: usize, length: usize) -> Vec<Vec<Range<usize>>> {
+        (0..lines)
+            .map(|line| {
+                let mut segments = Vec::new();
+                let mut start = 0;
+                for square in squares.ones(line).chain([length]) {
+                    if square > start {
+                        segments.push(start..square);
+                    }
+                    start = square + 1;
+                }
+                segments
+            })
+            .collect()
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    enum Layout {
+        /// One line per row, x along the line
+        Rows,
+        /// One line per column, y along the line
+        Columns,
+    }
+
+    #[derive(Debug, Clone)]
+    pub struct Platform {
+        width: usize,
+        height: usize,
+        row_segments: Vec<Vec<Range<usize>>>,
+        column_segments: Vec<Vec<Range<usize>>>,
+        round: Bits,
+        layout: Layout,
+    }
+    impl Platform {
+        pub fn new(grid: &Grid) -> Self {
+            let (width, height) = (grid[0].len(), grid.len());
+            let mut round = Bits::new(height, width);
+            let mut square = Bits::new(height, width);
+            for (y, line) in grid.iter().enumerate() {
+                for (x, rock) in line.iter().enumerate() {
+                    match rock {
+                        Some(Rock::Round) => round.set(y, x),
+                        Some(Rock::Square) => square.set(y, x),
+                        None => (),
+                    }
+                }
+            }
+            let square_columns = square.transpose(height, width);
+            Self {
+                width,
+                height,
+                row_segments: segments(&square, height, width),
+                column_segments: segments(&square_columns, width, height),
+                round,
+                layout: Layout::Rows,
+            }
+        }
+
+        fn set_layout(&mut self, layout: Layout) {
+            if self.layout == layout {
+                return;
+            }
+            self.round = match self.layout {
+                Layout::Rows => self.round.transpose(self.height, self.width),
+                Layout::Columns => self.round.transpose(self.width, self.height),
+            };
+            self.layout = layout;
+        }
+
+        pub fn tilt(&mut self, tilt: Tilt) {
+            let (layout, towards_start) = match tilt {
+                Tilt::North => (Layout::Columns, true),
+                Tilt::South => (Layout::Columns, false),
+                Tilt::West => (Layout::Rows, true),
+                Tilt::East => (Layout::Rows, false),
+            };
+            self.set_layout(layout);
+            let segments = match layout {
+                Layout::Rows => &self.row_segments,
+                Layout::Columns => &self.column_segments,
+            };
+            for (index, segments) in segments.iter().enumerate() {
+                let line = self.round.line_mut(index);
+                for segment in segments {
+                    Bits::pack(line, segment.clone(), towards_start);
+                }
+            }
+        }
+
+        pub fn cycle(&mut self) {
+            for tilt in [Tilt::North, Tilt::West, Tilt::South, Tilt::East] {
+                self.tilt(tilt);
+            }
+        }
+
+        /// Repeats the tilt `sequence` until a state comes back, with the load on `side` after every cycle
+        pub fn spin(&self, sequence: &[Tilt], side: Tilt) -> Dynamics {
+            let mut platform = self.clone();
+            platform.set_layout(Layout::Rows);
+            // Round rocks after each cycle
+            let mut history: Vec<Vec<u64>> = Vec::new();
+            let mut loads = Vec::new();
+            let mut seen: HashMap<u64, usize> = HashMap::new();
+            loop {
+                let hash = platform.hash();
+                if let Some(&loop_start) = seen.get(&hash) {
+                    if platform.round.words == history[loop_start] {
+                        return Dynamics {
+                            loop_length: history.len() - loop_start,
+                            loads,
+                            loop_start,
+                        };
+                    }
+                }
+                seen.insert(hash, history.len());
+                history.push(platform.round.words.clone());
+                loads.push(platform.load(side));
+                for tilt in sequence {
+                    platform.tilt(*tilt);
+                }
+                // States are only comparable in the same layout
+                platform.set_layout(Layout::Rows);
+            }
+        }
+
+        /// Coordinates (x, y) of the round rocks
+        pub fn round_rocks(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+            let lines = match self.layout {
+                Layout::Rows => self.height,
+                Layout::Columns => self.width,
+            };
+            (0..lines).flat_map(move |line| {
+                self.round.ones(line).map(move |index| match self.layout {
+                    Layout::Rows => (index, line),
+                    Layout::Columns => (line, index),
+                })
+            })
+        }
+
+        /// Load on the beams of one side : each round rock weighs its distance to the opposite side
+        pub fn load(&self, side: Tilt) -> usize {
+            self.round_rocks()
+                .map(|(x, y)| match side {
+                    Tilt::North => self.height - y,
+                    Tilt::South => y + 1,
+                    Tilt::West => self.width - x,
+                    Tilt::East => x + 1,
+                })
+                .sum()
+        }
+
+        /// Hash of the round rocks positions, the same whatever the current layout
+        pub fn hash(&self) -> u64 {
+            let rows;
+            let words = match self.layout {
+                Layout::Rows => &self.round.words,
+                Layout::Columns => {
+                    rows = self.round.transpose(self.width, self.height);
+                    &rows.words
+                }
+            };
+            words.iter().fold(0, |hash, word| {
+                (hash.rotate_left(5) ^ word).wrapping_mul(0x51_7c_c1_b7_27_22_0a_95)
+            })
+        }
+
+        pub fn to_grid(&self, grid: &Grid) -> Grid {
+            let mut grid = grid
+                .iter()
+                .map(|line| {
+                    line.iter()
+                        .map(|rock| rock.clone().filter(|rock| *rock == Rock::Square))
+                        .collect_vec()
+                })
+                .collect_vec();
+            for (x, y) in self.round_rocks() {
+                grid[y][x] = Some(Rock::Round);
+            }
+            grid
+        }
+    }
+}
+
+#[cfg(feature = "fuzzing")]
+pub fn fuzz_parsers(input: &str) {
+    let _ = parsers::grid(input);
+}
+
 pub mod parsers {
     use nom::{
         character::complete::{anychar, line_ending},
@@ -240,6 +666,114 @@ mod tests {
         Day::test_part2(64)
     }
 
+    #[test]
+    fn test_bitboard() {
+        for input in [Day::SAMPLE_PART1, Day::INPUT] {
+            let (_, mut grid) = parsers::grid(input).unwrap();
+            let mut platform = bitboard::Platform::new(&grid);
+            assert_eq!(grid, platform.to_grid(&grid));
+            for _ in 0..3 {
+                for (tilt, move_grid) in [
+                    (bitboard::Tilt::North, move_north as fn(&mut Grid)),
+                    (bitboard::Tilt::West, move_west),
+                    (bitboard::Tilt::South, move_south),
+                    (bitboard::Tilt::East, move_east),
+                ] {
+                    platform.tilt(tilt);
+                    move_grid(&mut grid);
+                    assert_eq!(grid, platform.to_grid(&grid));
+                    assert_eq!(count_load(&grid), platform.load(bitboard::Tilt::North));
+                }
+            }
+        }
+        assert_eq!(96003, bitboard::part2(Day::INPUT));
+        assert_eq!(96003, cell_by_cell::part2(Day::INPUT));
+    }
+
+    #[test]
+    fn test_spin() {
+        use bitboard::{InvalidTilt, Tilt};
+
+        let (_, grid) = parsers::grid(Day::SAMPLE_PART2).unwrap();
+        let platform = bitboard::Platform::new(&grid);
+        let dynamics = platform.spin(&Tilt::sequence("NWSE").unwrap(), Tilt::North);
+        assert_eq!((3, 7), (dynamics.loop_start, dynamics.loop_length));
+        assert_eq!(64, dynamics.load_after(1_000_000_000));
+        assert_eq!(
+            Ok(64),
+            bitboard::load_after(Day::SAMPLE_PART2, "NWSE", 1_000_000_000, Tilt::North)
+        );
+        assert_eq!(
+            Ok(136),
+            bitboard::load_after(Day::SAMPLE_PART1, "N", 1, Tilt::North)
+        );
+        assert_eq!(
+            Err(InvalidTilt('x')),
+            bitboard::load_after(Day::SAMPLE_PART1, "NxE", 1, Tilt::North)
+        );
+
+        // Sequences ending in N or S, from a state already in their loop
+        for sequence in ["NWSEN", "EWS"] {
+            let tilts = Tilt::sequence(sequence).unwrap();
+            let dynamics = platform.spin(&tilts, Tilt::North);
+            let mut grid = grid.clone();
+            for _ in 0..dynamics.loop_start {
+                apply(&mut grid, &tilts);
+            }
+            let periodic = bitboard::Platform::new(&grid).spin(&tilts, Tilt::North);
+            assert_eq!(
+                (0, dynamics.loop_length),
+                (periodic.loop_start, periodic.loop_length),
+                "{sequence}"
+            );
+        }
+
+        // From a periodic state, left in the column layout by its last tilt
+        let tilts = Tilt::sequence("WSEN").unwrap();
+        let dynamics = platform.spin(&tilts, Tilt::North);
+        let mut looped = grid.clone();
+        for _ in 0..dynamics.loop_start {
+            apply(&mut looped, &tilts);
+        }
+        let mut tilted = bitboard::Platform::new(&looped);
+        tilted.tilt(Tilt::North);
+        let periodic = tilted.spin(&tilts, Tilt::North);
+        assert_eq!(
+            (0, dynamics.loop_length),
+            (periodic.loop_start, periodic.loop_length)
+        );
+
+        // Any sequence and side, against the grid moves
+        for sequence in ["NNEE", "SW", "ENWS", "W", "NWSEN", ""] {
+            for side in [Tilt::North, Tilt::South, Tilt::West, Tilt::East] {
+                let tilts = Tilt::sequence(sequence).unwrap();
+                let dynamics = platform.spin(&tilts, side);
+                let mut grid = grid.clone();
+                for cycles in 0..20 {
+                    let expected = bitboard::Platform::new(&grid).load(side);
+                    assert_eq!(
+                        expected,
+                        dynamics.load_after(cycles),
+                        "{sequence} {side:?} {cycles}"
+                    );
+                    apply(&mut grid, &tilts);
+                }
+            }
+        }
+    }
+
+    fn apply(grid: &mut Grid, tilts: &[bitboard::Tilt]) {
+        use bitboard::Tilt;
+        for tilt in tilts {
+            match tilt {
+                Tilt::North => move_north(grid),
+                Tilt::South => move_south(grid),
+                Tilt::West => move_west(grid),
+                Tilt::East => move_east(grid),
+            }
+        }
+    }
+
     #[test]
     fn test_dual_thread() {
         let input = Day::INPUT;
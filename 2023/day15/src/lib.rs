@@ -1,7 +1,4 @@
-use std::{
-    array,
-    hash::{Hash, Hasher},
-};
+use std::hash::{Hash, Hasher};
 
 use aoc::Aoc;
 
@@ -18,78 +15,200 @@ impl Aoc for Day {
         let mut hasher = HolidayHasher::new();
         let mut total = 0;
         for byte in input.as_bytes() {
-            if *byte == b',' {
-                total += hasher.finish();
-                hasher = HolidayHasher::new();
-                continue;
+            match byte {
+                b',' => {
+                    total += hasher.finish();
+                    hasher = HolidayHasher::new();
+                }
+                // The puzzle ignores line endings
+                b'\r' | b'\n' => (),
+                _ => (*byte).hash(&mut hasher),
             }
-            (*byte).hash(&mut hasher);
         }
         total += hasher.finish();
         total
     }
 
     fn part2(mut input: &str) -> Self::OUTPUT {
-        let mut lensboxes: [Vec<(&str, u32)>; 256] = array::from_fn(|_| Vec::new());
+        let mut lensboxes = HolidayMap::new();
         while let Ok((remain, instruction)) = parsers::instruction(input) {
             input = remain;
 
-            let box_index = HolidayHasher::hash_str(instruction.label) as usize;
-            let lensbox = &mut lensboxes[box_index];
-
+            let label = Label(instruction.label);
             match instruction.action {
                 Action::AddLens(power) => {
-                    match lensbox.iter_mut().find(|(l, _)| *l == instruction.label) {
-                        Some((_, p)) => *p = power,
-                        None => lensbox.push((instruction.label, power)),
-                    }
+                    lensboxes.insert(label, power);
                 }
                 Action::RemoveLens => {
-                    if let Some(remove_index) =
-                        lensbox.iter().position(|(l, _)| *l == instruction.label)
-                    {
-                        lensbox.remove(remove_index);
-                    }
+                    lensboxes.remove(&label);
                 }
             }
         }
-        lensboxes
-            .into_iter()
-            .zip(1..)
-            .map(|(lensbox, box_number)| {
-                lensbox
-                    .into_iter()
-                    .zip(1..)
-                    .map(|((_, power), slot_number)| box_number * slot_number * power as u64)
-                    .sum::<u64>()
-            })
-            .sum::<u64>()
+        lensboxes.focusing_power()
     }
 }
 
-struct HolidayHasher(u8);
-impl Hasher for HolidayHasher {
-    fn finish(&self) -> u64 {
-        self.0 as u64
+pub use hashmap::{BuildHolidayHasher, HolidayHasher, HolidayMap, Label};
+
+/// The Holiday ASCII String Helper, and the Manual Arrangement Procedure built on it
+pub mod hashmap {
+    use std::hash::BuildHasher;
+
+    use super::*;
+
+    #[derive(Debug, Default, Clone, Copy)]
+    pub struct HolidayHasher(u8);
+    impl Hasher for HolidayHasher {
+        fn finish(&self) -> u64 {
+            self.0 as u64
+        }
+
+        fn write(&mut self, bytes: &[u8]) {
+            for byte in bytes {
+                self.0 = self.0.wrapping_add(*byte);
+                self.0 = self.0.wrapping_mul(17);
+            }
+        }
+    }
+    impl HolidayHasher {
+        pub fn new() -> Self {
+            Self(0)
+        }
+        pub fn hash_str(str: &str) -> u64 {
+            let mut hasher: HolidayHasher = HolidayHasher::new();
+            for b in str.as_bytes() {
+                (*b).hash(&mut hasher)
+            }
+            hasher.finish()
+        }
     }
 
-    fn write(&mut self, bytes: &[u8]) {
-        for byte in bytes {
-            self.0 = self.0.wrapping_add(*byte);
-            self.0 = self.0.wrapping_mul(17);
+    /// Creates `HolidayHasher`s for the std collections : `HashMap<K, V, BuildHolidayHasher>`
+    #[derive(Debug, Default, Clone, Copy)]
+    pub struct BuildHolidayHasher;
+    impl BuildHasher for BuildHolidayHasher {
+        type Hasher = HolidayHasher;
+
+        fn build_hasher(&self) -> Self::Hasher {
+            HolidayHasher::new()
         }
     }
-}
-impl HolidayHasher {
-    fn new() -> Self {
-        Self(0)
+
+    /// Lens label, hashed as its bytes only.
+    /// `str` also hashes a terminator byte, which would not give the puzzle's box numbers
+    #[derive(Debug, PartialEq, Eq, Clone, Copy)]
+    pub struct Label<'a>(pub &'a str);
+    impl Hash for Label<'_> {
+        fn hash<H: Hasher>(&self, state: &mut H) {
+            state.write(self.0.as_bytes())
+        }
+    }
+
+    /// Hash map keeping the entries of each box in insertion order :
+    /// inserting an existing key replaces its value in place, removing a key shifts the later entries
+    #[derive(Debug, Clone)]
+    pub struct HolidayMap<K, V, S = BuildHolidayHasher> {
+        boxes: Vec<Vec<(K, V)>>,
+        hasher: S,
+        len: usize,
+    }
+    impl<K: Hash + Eq, V> HolidayMap<K, V> {
+        /// 256 boxes, one for each `HolidayHasher` value
+        pub fn new() -> Self {
+            Self::with_hasher(256, BuildHolidayHasher)
+        }
     }
-    fn hash_str(str: &str) -> u64 {
-        let mut hasher: HolidayHasher = HolidayHasher::new();
-        for b in str.as_bytes() {
-            (*b).hash(&mut hasher)
+    impl<K: Hash + Eq, V> Default for HolidayMap<K, V> {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+    impl<K: Hash + Eq, V, S: BuildHasher> HolidayMap<K, V, S> {
+        pub fn with_hasher(boxes: usize, hasher: S) -> Self {
+            assert!(boxes > 0, "A HolidayMap needs at least one box");
+            Self {
+                boxes: (0..boxes).map(|_| Vec::new()).collect(),
+                hasher,
+                len: 0,
+            }
+        }
+
+        pub fn box_index(&self, key: &K) -> usize {
+            (self.hasher.hash_one(key) % self.boxes.len() as u64) as usize
+        }
+
+        /// Returns the previous value of the key, which keeps its slot
+        pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+            let index = self.box_index(&key);
+            let lensbox = &mut self.boxes[index];
+            match lensbox.iter_mut().find(|(k, _)| *k == key) {
+                Some((_, v)) => Some(std::mem::replace(v, value)),
+                None => {
+                    lensbox.push((key, value));
+                    self.len += 1;
+                    None
+                }
+            }
+        }
+
+        pub fn remove(&mut self, key: &K) -> Option<V> {
+            let index = self.box_index(key);
+            let lensbox = &mut self.boxes[index];
+            let position = lensbox.iter().position(|(k, _)| k == key)?;
+            self.len -= 1;
+            Some(lensbox.remove(position).1)
+        }
+
+        pub fn get(&self, key: &K) -> Option<&V> {
+            let lensbox = &self.boxes[self.box_index(key)];
+            lensbox.iter().find(|(k, _)| k == key).map(|(_, v)| v)
+        }
+
+        pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+            let index = self.box_index(key);
+            let lensbox = &mut self.boxes[index];
+            lensbox.iter_mut().find(|(k, _)| k == key).map(|(_, v)| v)
+        }
+
+        pub fn contains_key(&self, key: &K) -> bool {
+            self.get(key).is_some()
+        }
+    }
+    impl<K, V, S> HolidayMap<K, V, S> {
+        pub fn len(&self) -> usize {
+            self.len
+        }
+
+        pub fn is_empty(&self) -> bool {
+            self.len == 0
+        }
+
+        /// Every box, including the empty ones, with its entries in slot order
+        pub fn boxes(&self) -> impl Iterator<Item = &[(K, V)]> {
+            self.boxes.iter().map(Vec::as_slice)
+        }
+
+        /// Entries of all the boxes, box by box
+        pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+            self.boxes.iter().flatten().map(|(k, v)| (k, v))
+        }
+
+        /// Sum of box number * slot number * value, both numbers starting at 1
+        pub fn focusing_power(&self) -> u64
+        where
+            V: Copy + Into<u64>,
+        {
+            self.boxes()
+                .zip(1..)
+                .map(|(lensbox, box_number)| {
+                    lensbox
+                        .iter()
+                        .zip(1..)
+                        .map(|((_, power), slot_number)| box_number * slot_number * (*power).into())
+                        .sum::<u64>()
+                })
+                .sum::<u64>()
         }
-        hasher.finish()
     }
 }
 
@@ -104,6 +223,14 @@ enum Action {
     RemoveLens,
 }
 
+/// Chains the instructions like the solvers do, skipping the ',' after each one
+#[cfg(feature = "fuzzing")]
+pub fn fuzz_parsers(mut input: &str) {
+    while let Ok((remain, _)) = parsers::instruction(input) {
+        input = remain;
+    }
+}
+
 mod parsers {
     use nom::{
         character::complete::{alpha1, anychar, u32},
@@ -160,4 +287,62 @@ mod tests {
     fn test_part2() {
         Day::test_part2(145)
     }
+
+    #[test]
+    fn test_holiday_map() {
+        let mut map = HolidayMap::new();
+        assert_eq!(None, map.insert(Label("rn"), 1_u32));
+        assert_eq!(None, map.remove(&Label("cm")));
+        map.insert(Label("qp"), 3);
+        map.insert(Label("cm"), 2);
+        assert_eq!(Some(3), map.remove(&Label("qp")));
+        map.insert(Label("pc"), 4);
+        map.insert(Label("ot"), 9);
+        map.insert(Label("ab"), 5);
+        map.remove(&Label("pc"));
+        map.insert(Label("pc"), 6);
+        assert_eq!(Some(9), map.insert(Label("ot"), 7));
+
+        assert_eq!(5, map.len());
+        assert_eq!(Some(&7), map.get(&Label("ot")));
+        assert!(!map.contains_key(&Label("qp")));
+        let boxes = map.boxes().collect::<Vec<_>>();
+        assert_eq!(256, boxes.len());
+        assert_eq!(&[(Label("rn"), 1), (Label("cm"), 2)], boxes[0]);
+        assert_eq!(
+            &[(Label("ot"), 7), (Label("ab"), 5), (Label("pc"), 6)],
+            boxes[3]
+        );
+        assert_eq!(
+            vec!["rn", "cm", "ot", "ab", "pc"],
+            map.iter().map(|(label, _)| label.0).collect::<Vec<_>>()
+        );
+        assert_eq!(145, map.focusing_power());
+    }
+
+    #[test]
+    fn test_build_hasher() {
+        use std::{collections::HashMap, hash::BuildHasher};
+
+        assert_eq!(0, BuildHolidayHasher.hash_one(Label("rn")));
+        assert_eq!(3, BuildHolidayHasher.hash_one(Label("pc")));
+
+        let mut std_map = HashMap::with_hasher(BuildHolidayHasher);
+        for (label, power) in [("rn", 1), ("cm", 2), ("ot", 7)] {
+            std_map.insert(Label(label), power);
+        }
+        assert_eq!(Some(&2), std_map.get(&Label("cm")));
+
+        // Any hasher and number of boxes
+        let mut map = HolidayMap::with_hasher(4, std::hash::RandomState::new());
+        for i in 0..100_u32 {
+            map.insert(i, i);
+        }
+        for i in (0..100).step_by(2) {
+            map.remove(&i);
+        }
+        assert_eq!(50, map.len());
+        assert_eq!(4, map.boxes().count());
+        assert!(map.iter().all(|(k, v)| k == v && k % 2 == 1));
+    }
 }
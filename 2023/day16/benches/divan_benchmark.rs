@@ -38,4 +38,11 @@ mod part2 {
             .with_inputs(|| parsers::tile_map(Day::INPUT).unwrap().1)
             .bench_values(|tiles| day16::brute_force::compute_part2(tiles))
     }
+
+    #[divan::bench]
+    fn segments(bencher: Bencher) {
+        bencher
+            .with_inputs(|| parsers::tile_map(Day::INPUT).unwrap().1)
+            .bench_values(|tiles| day16::segments::Contraption::new(&tiles).part2())
+    }
 }
@@ -19,7 +19,7 @@ impl Aoc for Day {
     fn part2(input: &str) -> Self::OUTPUT {
         let (_, mirrors) =
             parsers::tile_map(input).unwrap_or_else(|e| panic!("Parser failed {e:?}"));
-        brute_force::compute_part2(mirrors)
+        segments::Contraption::new(&mirrors).part2()
     }
 }
 
@@ -53,7 +53,26 @@ impl Tile {
         }
 
         *self |= input_ray;
+        self.outgoing(input_ray)
+    }
+
+    /// Character of the mirror or splitter in the tile, as in the puzzle input
+    pub fn mirror(self) -> Option<char> {
+        if self.contains(Self::MirrorNE) {
+            Some('/')
+        } else if self.contains(Self::MirrorSE) {
+            Some('\\')
+        } else if self.contains(Self::SplitterNS) {
+            Some('|')
+        } else if self.contains(Self::SplitterEW) {
+            Some('-')
+        } else {
+            None
+        }
+    }
 
+    /// Rays leaving the tile, without recording the input ray
+    fn outgoing(self, input_ray: Self) -> Self {
         // Go straight
         if !self.intersects(Self::AllMirrors) {
             return input_ray;
@@ -85,6 +104,234 @@ impl Tile {
     }
 }
 
+/// Beams only branch when they hit the flat side of a splitter.
+/// Between two splitters, a beam follows a single segment, through mirrors and along splitters.
+/// The splitters form a graph, and all the splitters of a strongly connected component
+/// energise the same tiles : these sets are computed once per component, successors first.
+pub mod segments {
+    use super::*;
+
+    /// Segment followed by a beam, and the splitter it ends on
+    struct Segment {
+        tiles: Vec<usize>,
+        splitter: Option<usize>,
+    }
+
+    pub struct Contraption {
+        width: usize,
+        height: usize,
+        tiles: Vec<Tile>,
+        /// Index of the splitters in `energised`, by tile
+        components: Vec<Option<usize>>,
+        /// Tiles energised by a beam hitting a splitter of each component, as a bitset
+        energised: Vec<Vec<u64>>,
+    }
+    impl Contraption {
+        pub fn new(tiles: &TileMap) -> Self {
+            let mut contraption = Self {
+                width: tiles[0].len(),
+                height: tiles.len(),
+                tiles: tiles.concat(),
+                components: Vec::new(),
+                energised: Vec::new(),
+            };
+
+            // Splitters, with the tiles energised before hitting the next splitters
+            let splitters = (0..contraption.tiles.len())
+                .filter(|index| {
+                    contraption.tiles[*index].intersects(Tile::SplitterEW | Tile::SplitterNS)
+                })
+                .collect::<Vec<_>>();
+            let mut node = vec![None; contraption.tiles.len()];
+            for (id, index) in splitters.iter().enumerate() {
+                node[*index] = Some(id);
+            }
+            let mut own_tiles = Vec::new();
+            let mut successors = Vec::new();
+            for index in &splitters {
+                let rays = match contraption.tiles[*index].contains(Tile::SplitterEW) {
+                    true => [Tile::RayEast, Tile::RayWest],
+                    false => [Tile::RayNorth, Tile::RaySouth],
+                };
+                let mut tiles = vec![*index];
+                let mut next = Vec::new();
+                for ray in rays {
+                    let Some(start) = contraption.step(*index, ray) else {
+                        continue;
+                    };
+                    let segment = contraption.segment(start, ray);
+                    tiles.extend(segment.tiles);
+                    next.extend(segment.splitter.map(|s| node[s].unwrap()));
+                }
+                own_tiles.push(tiles);
+                successors.push(next);
+            }
+
+            let (component_of, components) = tarjan(&successors);
+            let words = contraption.tiles.len().div_ceil(64);
+            let mut energised = vec![Vec::new(); components.len()];
+            // Tarjan's algorithm completes the successors components first
+            for (component, nodes) in components.iter().enumerate() {
+                let mut set = vec![0_u64; words];
+                for node in nodes {
+                    for tile in &own_tiles[*node] {
+                        set[tile / 64] |= 1 << (tile % 64);
+                    }
+                    for next in &successors[*node] {
+                        if component_of[*next] != component {
+                            for (word, other) in set.iter_mut().zip(&energised[component_of[*next]])
+                            {
+                                *word |= other;
+                            }
+                        }
+                    }
+                }
+                energised[component] = set;
+            }
+
+            contraption.components = node
+                .iter()
+                .map(|node| node.map(|node| component_of[node]))
+                .collect();
+            contraption.energised = energised;
+            contraption
+        }
+
+        /// Next tile in the direction of the ray
+        fn step(&self, index: usize, ray: Tile) -> Option<usize> {
+            let (x, y) = (index % self.width, index / self.width);
+            match ray {
+                Tile::RayEast if x != self.width - 1 => Some(index + 1),
+                Tile::RayWest if x != 0 => Some(index - 1),
+                Tile::RayNorth if y != 0 => Some(index - self.width),
+                Tile::RaySouth if y != self.height - 1 => Some(index + self.width),
+                _ => None,
+            }
+        }
+
+        /// Follows a beam entering a tile until it splits, leaves the grid, or loops.
+        /// Without splits, each state has a single predecessor : the only loop goes back to the start
+        fn segment(&self, start: usize, ray: Tile) -> Segment {
+            let mut tiles = Vec::new();
+            let (mut index, mut ray, start_ray) = (start, ray, ray);
+            loop {
+                tiles.push(index);
+                let outgoing = self.tiles[index].outgoing(ray);
+                if outgoing.bits().count_ones() == 2 {
+                    return Segment {
+                        tiles,
+                        splitter: Some(index),
+                    };
+                }
+                ray = outgoing;
+                match self.step(index, ray) {
+                    Some(next) if (next, ray) == (start, start_ray) => break,
+                    Some(next) => index = next,
+                    None => break,
+                }
+            }
+            Segment {
+                tiles,
+                splitter: None,
+            }
+        }
+
+        /// Number of tiles energised by a beam entering the tile at `coords`
+        pub fn energised_count(&self, ray: Tile, (x, y): (usize, usize)) -> usize {
+            let segment = self.segment(y * self.width + x, ray);
+            let mut set = match segment.splitter {
+                Some(splitter) => self.energised[self.components[splitter].unwrap()].clone(),
+                None => vec![0; self.tiles.len().div_ceil(64)],
+            };
+            for tile in segment.tiles {
+                set[tile / 64] |= 1 << (tile % 64);
+            }
+            set.iter().map(|word| word.count_ones() as usize).sum()
+        }
+
+        /// Every entry from the border, and the number of tiles it energises
+        pub fn border_counts(&self) -> impl Iterator<Item = ((Tile, (usize, usize)), usize)> + '_ {
+            border_entries(self.width, self.height)
+                .map(|(ray, coords)| ((ray, coords), self.energised_count(ray, coords)))
+        }
+
+        pub fn part2(&self) -> usize {
+            self.border_counts().map(|(_, count)| count).max().unwrap()
+        }
+    }
+
+    /// Strongly connected components : the component of every node, and the nodes of every component.
+    /// Iterative, components are numbered in completion order, successors first
+    fn tarjan(successors: &[Vec<usize>]) -> (Vec<usize>, Vec<Vec<usize>>) {
+        let count = successors.len();
+        let mut index = vec![usize::MAX; count];
+        let mut low = vec![0; count];
+        let mut on_stack = vec![false; count];
+        let mut stack = Vec::new();
+        let mut component_of = vec![usize::MAX; count];
+        let mut components = Vec::new();
+        let mut next_index = 0;
+
+        for root in 0..count {
+            if index[root] != usize::MAX {
+                continue;
+            }
+            // (node, next successor to visit)
+            let mut path = vec![(root, 0)];
+            index[root] = next_index;
+            low[root] = next_index;
+            next_index += 1;
+            stack.push(root);
+            on_stack[root] = true;
+            while let Some((node, child)) = path.last_mut() {
+                let node = *node;
+                if let Some(next) = successors[node].get(*child).copied() {
+                    *child += 1;
+                    if index[next] == usize::MAX {
+                        index[next] = next_index;
+                        low[next] = next_index;
+                        next_index += 1;
+                        stack.push(next);
+                        on_stack[next] = true;
+                        path.push((next, 0));
+                    } else if on_stack[next] {
+                        low[node] = low[node].min(index[next]);
+                    }
+                    continue;
+                }
+                path.pop();
+                if let Some((parent, _)) = path.last() {
+                    low[*parent] = low[*parent].min(low[node]);
+                }
+                if low[node] == index[node] {
+                    let mut component = Vec::new();
+                    while let Some(member) = stack.pop() {
+                        on_stack[member] = false;
+                        component_of[member] = components.len();
+                        component.push(member);
+                        if member == node {
+                            break;
+                        }
+                    }
+                    components.push(component);
+                }
+            }
+        }
+        (component_of, components)
+    }
+
+    pub fn border_entries(
+        width: usize,
+        height: usize,
+    ) -> impl Iterator<Item = (Tile, (usize, usize))> {
+        let top = (0..width).map(move |x| (Tile::RaySouth, (x, 0)));
+        let bottom = (0..width).map(move |x| (Tile::RayNorth, (x, height - 1)));
+        let left = (0..height).map(move |y| (Tile::RayEast, (0, y)));
+        let right = (0..height).map(move |y| (Tile::RayWest, (width - 1, y)));
+        top.chain(bottom).chain(left).chain(right)
+    }
+}
+
 pub mod brute_force {
     use super::*;
     pub fn part1(input: &str) -> usize {
@@ -92,11 +339,16 @@ pub mod brute_force {
         compute_path(Tile::RayEast, (0, 0), tiles)
     }
 
-    pub fn compute_path(
-        start_ray: Tile,
-        start_coords: (usize, usize),
-        mut tiles: TileMap,
-    ) -> usize {
+    pub fn compute_path(start_ray: Tile, start_coords: (usize, usize), tiles: TileMap) -> usize {
+        energise(start_ray, start_coords, tiles)
+            .iter()
+            .flat_map(|line| line.iter())
+            .filter(|tile| tile.intersects(Tile::AllRays))
+            .count()
+    }
+
+    /// Follows the beam entering the tile at `start_coords`, and returns the tiles with the rays going through them
+    pub fn energise(start_ray: Tile, start_coords: (usize, usize), mut tiles: TileMap) -> TileMap {
         let height = tiles.len();
         let width = tiles[0].len();
 
@@ -114,12 +366,7 @@ pub mod brute_force {
                 beams.push((next_ray, next_coords));
             }
         }
-
         tiles
-            .iter()
-            .flat_map(|line| line.iter())
-            .filter(|tile| tile.intersects(Tile::AllRays))
-            .count()
     }
 
     pub fn compute_part2(tiles: TileMap) -> usize {
@@ -131,16 +378,182 @@ pub mod brute_force {
     }
 
     fn starting_positions(tiles: &TileMap) -> impl Iterator<Item = (Tile, (usize, usize))> {
-        let height = tiles.len();
-        let width = tiles[0].len();
-        let top = (0..width).map(move |x| (Tile::RaySouth, (x, 0)));
-        let bottom = (0..width).map(move |x| (Tile::RayNorth, (x, height - 1)));
-        let left = (0..height).map(move |y| (Tile::RayEast, (0, y)));
-        let right = (0..height).map(move |y| (Tile::RayWest, (width - 1, y)));
-        top.chain(bottom).chain(left).chain(right)
+        segments::border_entries(tiles[0].len(), tiles.len())
     }
 }
 
+/// Text and SVG renderings of the energised tiles, and of the energised counts around the border
+pub mod render {
+    use super::*;
+    use std::fmt::Write;
+
+    /// Mirrors keep their character, energised empty tiles show the ray direction,
+    /// or the number of rays when several cross
+    pub fn text(tiles: &TileMap) -> String {
+        let mut output = String::new();
+        for line in tiles {
+            for tile in line {
+                let c = match (tile.mirror(), *tile & Tile::AllRays) {
+                    (Some(c), _) => c,
+                    (None, Tile::RayNorth) => '^',
+                    (None, Tile::RaySouth) => 'v',
+                    (None, Tile::RayEast) => '>',
+                    (None, Tile::RayWest) => '<',
+                    (None, rays) if rays.is_empty() => '.',
+                    (None, rays) => char::from_digit(rays.bits().count_ones(), 10).unwrap(),
+                };
+                output.push(c);
+            }
+            output.push('\n');
+        }
+        output
+    }
+
+    /// Energised tiles in yellow, below the mirrors
+    pub fn svg(tiles: &TileMap) -> String {
+        let (width, height) = (tiles[0].len(), tiles.len());
+        let mut svg = header(0, width, height);
+        for (y, line) in tiles.iter().enumerate() {
+            for (x, tile) in line.iter().enumerate() {
+                if tile.intersects(Tile::AllRays) {
+                    writeln!(
+                        svg,
+                        r##"<rect x="{x}" y="{y}" width="1" height="1" fill="#fc0"/>"##
+                    )
+                    .unwrap();
+                }
+            }
+        }
+        mirrors(&mut svg, tiles);
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    /// Number of tiles energised by each entry point, on each side of the grid
+    pub struct Heatmap {
+        /// Beams entering from the top, going south
+        pub north: Vec<usize>,
+        pub south: Vec<usize>,
+        pub west: Vec<usize>,
+        pub east: Vec<usize>,
+    }
+    impl Heatmap {
+        pub fn new(tiles: &TileMap) -> Self {
+            let (width, height) = (tiles[0].len(), tiles.len());
+            let mut heatmap = Self {
+                north: vec![0; width],
+                south: vec![0; width],
+                west: vec![0; height],
+                east: vec![0; height],
+            };
+            let contraption = segments::Contraption::new(tiles);
+            for ((ray, (x, y)), count) in contraption.border_counts() {
+                match ray {
+                    Tile::RaySouth => heatmap.north[x] = count,
+                    Tile::RayNorth => heatmap.south[x] = count,
+                    Tile::RayEast => heatmap.west[y] = count,
+                    _ => heatmap.east[y] = count,
+                }
+            }
+            heatmap
+        }
+
+        pub fn max(&self) -> usize {
+            [&self.north, &self.south, &self.west, &self.east]
+                .into_iter()
+                .flatten()
+                .copied()
+                .max()
+                .unwrap_or(0)
+        }
+
+        /// The grid surrounded by its entry points, shaded from ' ' (no tiles) to '@' (the maximum)
+        pub fn text(&self, tiles: &TileMap) -> String {
+            const SHADES: &[u8] = b" .:-=+*#%@";
+            let max = self.max().max(1);
+            let shade = |count: usize| SHADES[count * (SHADES.len() - 1) / max] as char;
+            let border = |counts: &[usize]| {
+                let mut line = String::from(" ");
+                line.extend(counts.iter().map(|count| shade(*count)));
+                line.push_str(" \n");
+                line
+            };
+
+            let mut output = border(&self.north);
+            for (y, line) in tiles.iter().enumerate() {
+                output.push(shade(self.west[y]));
+                output.extend(line.iter().map(|tile| tile.mirror().unwrap_or('.')));
+                output.push(shade(self.east[y]));
+                output.push('\n');
+            }
+            output.push_str(&border(&self.south));
+            output
+        }
+
+        /// The grid surrounded by its entry points, coloured from blue (no tiles) to red (the maximum).
+        /// Hovering an entry point shows its count
+        pub fn svg(&self, tiles: &TileMap) -> String {
+            let (width, height) = (tiles[0].len(), tiles.len());
+            let max = self.max().max(1);
+            let mut svg = header(1, width, height);
+            let sides = [
+                (&self.north, (0, -1), (1, 0)),
+                (&self.south, (0, height as isize), (1, 0)),
+                (&self.west, (-1, 0), (0, 1)),
+                (&self.east, (width as isize, 0), (0, 1)),
+            ];
+            for (counts, (x0, y0), (dx, dy)) in sides {
+                for (i, count) in counts.iter().enumerate() {
+                    let (x, y) = (x0 + dx * i as isize, y0 + dy * i as isize);
+                    let hue = 240 - 240 * count / max;
+                    writeln!(
+                        svg,
+                        r#"<rect x="{x}" y="{y}" width="1" height="1" fill="hsl({hue}, 100%, 50%)"><title>{count}</title></rect>"#
+                    )
+                    .unwrap();
+                }
+            }
+            mirrors(&mut svg, tiles);
+            svg.push_str("</svg>\n");
+            svg
+        }
+    }
+
+    /// Opening tag, with a margin around the grid, and a white background
+    fn header(margin: usize, width: usize, height: usize) -> String {
+        let (w, h) = (width + 2 * margin, height + 2 * margin);
+        format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"-{margin} -{margin} {w} {h}\" width=\"{}\" height=\"{}\">\n\
+            <rect x=\"-{margin}\" y=\"-{margin}\" width=\"{w}\" height=\"{h}\" fill=\"white\"/>\n",
+            w * 8,
+            h * 8
+        )
+    }
+
+    fn mirrors(svg: &mut String, tiles: &TileMap) {
+        svg.push_str(r#"<g stroke="black" stroke-width="0.15">"#);
+        svg.push('\n');
+        for (y, line) in tiles.iter().enumerate() {
+            for (x, tile) in line.iter().enumerate() {
+                let (x1, y1, x2, y2) = match tile.mirror() {
+                    Some('/') => (x as f32, y as f32 + 1.0, x as f32 + 1.0, y as f32),
+                    Some('\\') => (x as f32, y as f32, x as f32 + 1.0, y as f32 + 1.0),
+                    Some('|') => (x as f32 + 0.5, y as f32, x as f32 + 0.5, y as f32 + 1.0),
+                    Some('-') => (x as f32, y as f32 + 0.5, x as f32 + 1.0, y as f32 + 0.5),
+                    _ => continue,
+                };
+                writeln!(svg, r#"<line x1="{x1}" y1="{y1}" x2="{x2}" y2="{y2}"/>"#).unwrap();
+            }
+        }
+        svg.push_str("</g>\n");
+    }
+}
+
+#[cfg(feature = "fuzzing")]
+pub fn fuzz_parsers(input: &str) {
+    let _ = parsers::tile_map(input);
+}
+
 pub mod parsers {
     use nom::{
         character::complete::{anychar, line_ending},
@@ -202,4 +615,67 @@ mod tests {
         let tiles = parsers::tile_map(input).unwrap().1;
         assert_eq!(8314, brute_force::compute_part2(tiles));
     }
+
+    #[test]
+    fn test_render() {
+        let tiles = parsers::tile_map(Day::SAMPLE_PART1).unwrap().1;
+        let energised = brute_force::energise(Tile::RayEast, (0, 0), tiles.clone());
+        let hashes = energised
+            .iter()
+            .map(|line| {
+                line.iter()
+                    .map(|t| {
+                        if t.intersects(Tile::AllRays) {
+                            '#'
+                        } else {
+                            '.'
+                        }
+                    })
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>();
+        let expected = "\
+######....
+.#...#....
+.#...#####
+.#...##...
+.#...##...
+.#...##...
+.#..####..
+########..
+.#######..
+.#...#.#..";
+        assert_eq!(hashes.join("\n"), expected);
+
+        let text = render::text(&energised);
+        assert_eq!(text.lines().next(), Some(r">|<<<\...."));
+        assert_eq!(text.lines().nth(7), Some(r"<->-/vv|.."));
+
+        let mirrors = tiles.iter().flatten().filter(|t| t.mirror().is_some());
+        let svg = render::svg(&energised);
+        assert_eq!(46, svg.matches("fill=\"#fc0\"").count());
+        assert_eq!(mirrors.count(), svg.matches("<line").count());
+
+        let heatmap = render::Heatmap::new(&tiles);
+        assert_eq!(51, heatmap.max());
+        assert_eq!(46, heatmap.west[0]);
+        assert_eq!(51, heatmap.north[3]);
+        let text = heatmap.text(&tiles);
+        assert_eq!(12, text.lines().count());
+        assert_eq!(Some('@'), text.lines().next().unwrap().chars().nth(4));
+        let svg = heatmap.svg(&tiles);
+        assert_eq!(40, svg.matches("<title>").count());
+    }
+
+    #[test]
+    fn test_segments() {
+        for input in [Day::SAMPLE_PART1, Day::INPUT] {
+            let tiles = parsers::tile_map(input).unwrap().1;
+            let contraption = segments::Contraption::new(&tiles);
+            for ((ray, coords), count) in contraption.border_counts() {
+                let expected = brute_force::compute_path(ray, coords, tiles.clone());
+                assert_eq!(count, expected, "{ray:?} {coords:?}");
+            }
+        }
+    }
 }
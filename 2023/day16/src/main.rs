@@ -1,6 +1,25 @@
 use aoc::Aoc;
-use day16::Day;
+use day16::{brute_force, parsers, render, Day, Tile};
 
 fn main() {
-    Day::main()
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    let tiles = || parsers::tile_map(Day::INPUT).unwrap().1;
+    match args.iter().map(String::as_str).collect::<Vec<_>>()[..] {
+        ["energised", ref format @ ..] => {
+            let energised = brute_force::energise(Tile::RayEast, (0, 0), tiles());
+            match format {
+                ["svg"] => print!("{}", render::svg(&energised)),
+                _ => print!("{}", render::text(&energised)),
+            }
+        }
+        ["heatmap", ref format @ ..] => {
+            let tiles = tiles();
+            let heatmap = render::Heatmap::new(&tiles);
+            match format {
+                ["svg"] => print!("{}", heatmap.svg(&tiles)),
+                _ => print!("{}", heatmap.text(&tiles)),
+            }
+        }
+        _ => Day::main(),
+    }
 }
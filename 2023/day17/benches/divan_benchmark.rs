@@ -1,5 +1,8 @@
 use aoc::Aoc;
-use day17::Day;
+use day17::{
+    solver::{Rules, Search},
+    Day,
+};
 use divan::{Bencher, Divan};
 use std::time::Duration;
 fn main() {
@@ -33,6 +36,30 @@ mod part1 {
             day17::using_pathfinding::astar(&heatmap, 1, 3)
         })
     }
+
+    #[divan::bench]
+    fn bucket_dijkstra(bencher: Bencher) {
+        bencher.with_inputs(|| Day::INPUT).bench_values(|input| {
+            let heatmap = day17::parsers::heat_map(input);
+            day17::bucket::dijkstra(&heatmap, 1, 3)
+        })
+    }
+
+    #[divan::bench]
+    fn solver_dijkstra(bencher: Bencher) {
+        bencher.with_inputs(|| Day::INPUT).bench_values(|input| {
+            let heatmap = day17::parsers::heat_map(input);
+            day17::solver::solve(&heatmap, &Rules::CRUCIBLE, Search::Dijkstra)
+        })
+    }
+
+    #[divan::bench]
+    fn solver_astar(bencher: Bencher) {
+        bencher.with_inputs(|| Day::INPUT).bench_values(|input| {
+            let heatmap = day17::parsers::heat_map(input);
+            day17::solver::solve(&heatmap, &Rules::CRUCIBLE, Search::AStar)
+        })
+    }
 }
 
 mod part2 {
@@ -59,4 +86,69 @@ mod part2 {
             day17::using_pathfinding::astar(&heatmap, 4, 10)
         })
     }
+
+    #[divan::bench]
+    fn bucket_dijkstra(bencher: Bencher) {
+        bencher.with_inputs(|| Day::INPUT).bench_values(|input| {
+            let heatmap = day17::parsers::heat_map(input);
+            day17::bucket::dijkstra(&heatmap, 4, 10)
+        })
+    }
+
+    #[divan::bench]
+    fn solver_dijkstra(bencher: Bencher) {
+        bencher.with_inputs(|| Day::INPUT).bench_values(|input| {
+            let heatmap = day17::parsers::heat_map(input);
+            day17::solver::solve(&heatmap, &Rules::ULTRA_CRUCIBLE, Search::Dijkstra)
+        })
+    }
+
+    #[divan::bench]
+    fn solver_astar(bencher: Bencher) {
+        bencher.with_inputs(|| Day::INPUT).bench_values(|input| {
+            let heatmap = day17::parsers::heat_map(input);
+            day17::solver::solve(&heatmap, &Rules::ULTRA_CRUCIBLE, Search::AStar)
+        })
+    }
+}
+
+mod generated {
+    use day17::{bucket, generator, parsers};
+
+    use super::*;
+    #[divan::bench]
+    fn part1_255x255(bencher: Bencher) {
+        bencher
+            .with_inputs(|| generator::input(0, 255, 255))
+            .bench_values(|input| Day::part1(&input))
+    }
+
+    #[divan::bench]
+    fn part2_255x255(bencher: Bencher) {
+        bencher
+            .with_inputs(|| generator::input(0, 255, 255))
+            .bench_values(|input| Day::part2(&input))
+    }
+
+    #[divan::bench]
+    fn bucket_part1_255x255(bencher: Bencher) {
+        bencher
+            .with_inputs(|| parsers::heat_map(&generator::input(0, 255, 255)))
+            .bench_values(|heatmap| bucket::dijkstra(&heatmap, 1, 3))
+    }
+
+    #[divan::bench]
+    fn bucket_part2_255x255(bencher: Bencher) {
+        bencher
+            .with_inputs(|| parsers::heat_map(&generator::input(0, 255, 255)))
+            .bench_values(|heatmap| bucket::dijkstra(&heatmap, 4, 10))
+    }
+
+    /// Too large for the other variants
+    #[divan::bench]
+    fn bucket_part2_1000x1000(bencher: Bencher) {
+        bencher
+            .with_inputs(|| parsers::heat_map(&generator::input(0, 1000, 1000)))
+            .bench_values(|heatmap| bucket::dijkstra(&heatmap, 4, 10))
+    }
 }
@@ -98,32 +98,12 @@ pub mod using_pathfinding {
         let success = |c: &Crucible| {
             c.x == width as u8 - 1 && c.y == height as u8 - 1 && c.moves >= min_moves
         };
-        let heuristic_map = {
-            let mut map = heatmap.clone();
-            for y in (0..height).rev() {
-                for x in (0..width).rev() {
-                    if x == width - 1 && y == height - 1 {
-                        continue;
-                    }
-                    let below = if y != height - 1 {
-                        heatmap[y + 1][x]
-                    } else {
-                        u32::MAX
-                    };
-                    let right = if x != width - 1 {
-                        heatmap[y][x + 1]
-                    } else {
-                        u32::MAX
-                    };
-                    map[y][x] += below.min(right);
-                }
-            }
-            map
-        };
+        // Every remaining tile costs at least the smallest heat in the map :
+        // the Manhattan distance scaled by that heat never overestimates the remaining cost
+        let min_heat = heatmap.iter().flatten().min().copied().unwrap_or(0);
         let heuristic = |c: &Crucible| {
-            let x = c.x as usize;
-            let y = c.y as usize;
-            heuristic_map[y][x]
+            let distance = (width - 1 - c.x as usize) + (height - 1 - c.y as usize);
+            distance as u32 * min_heat
         };
         let res = pathfinding::directed::astar::astar(&start, successors, heuristic, success);
         res.unwrap().1
@@ -202,7 +182,7 @@ impl Crucible {
     }
 }
 
-#[derive(Debug, Default, PartialEq, Eq, Clone, Copy, Hash)]
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy, Hash, PartialOrd, Ord)]
 pub enum Direction {
     North = 0,
     #[default]
@@ -229,6 +209,337 @@ impl Direction {
     }
 }
 
+/// Dial's algorithm : the heat lost on a tile is a small integer,
+/// so the queue is a ring of buckets, one per heat value, instead of a binary heap.
+/// States are packed into an index of a dense visited bitmap, with no limit on the grid size.
+pub mod bucket {
+    pub fn dijkstra(heatmap: &[Vec<u32>], min_moves: u8, max_moves: u8) -> u32 {
+        let height = heatmap.len();
+        let width = heatmap[0].len();
+        let heats = heatmap.concat();
+        let end = (width - 1, height - 1);
+
+        // State index : ((y * width + x) * 4 + direction) * (max_moves + 1) + moves
+        let move_states = max_moves as usize + 1;
+        let pack = |x: usize, y: usize, direction: usize, moves: usize| {
+            ((y * width + x) * 4 + direction) * move_states + moves
+        };
+        let unpack = |index: usize| {
+            let moves = index % move_states;
+            let index = index / move_states;
+            let direction = index % 4;
+            let tile = index / 4;
+            (tile % width, tile / width, direction, moves)
+        };
+        let mut visited = vec![0_u64; (width * height * 4 * move_states).div_ceil(64)];
+
+        let ring = heats.iter().max().copied().unwrap_or(0) as usize + 1;
+        let mut buckets = vec![Vec::new(); ring];
+        // Start with no direction (moves = 0)
+        buckets[0].push(pack(0, 0, 0, 0));
+        let mut pending = 1;
+        let mut heat = 0;
+        loop {
+            assert!(pending != 0, "Queue has run out of nodes");
+            let bucket = heat as usize % ring;
+            while let Some(state) = buckets[bucket].pop() {
+                pending -= 1;
+                let (x, y, direction, moves) = unpack(state);
+                // North, East, South, West
+                let choices = if moves == 0 {
+                    [Some(0), Some(1), Some(2), Some(3)]
+                } else {
+                    let turn = moves >= min_moves as usize;
+                    [
+                        (moves < max_moves as usize).then_some(direction),
+                        turn.then_some((direction + 3) % 4),
+                        turn.then_some((direction + 1) % 4),
+                        None,
+                    ]
+                };
+                for next in choices.into_iter().flatten() {
+                    let (nx, ny) = match next {
+                        0 if y != 0 => (x, y - 1),
+                        1 if x != width - 1 => (x + 1, y),
+                        2 if y != height - 1 => (x, y + 1),
+                        3 if x != 0 => (x - 1, y),
+                        _ => continue,
+                    };
+                    let next_moves = if next == direction && moves != 0 {
+                        moves + 1
+                    } else {
+                        1
+                    };
+                    let index = pack(nx, ny, next, next_moves);
+                    let (word, bit) = (index / 64, 1 << (index % 64));
+                    if visited[word] & bit != 0 {
+                        continue;
+                    }
+                    visited[word] |= bit;
+                    // The heat of a state only depends on the tile : the first visit is the best one
+                    let next_heat = heat + heats[ny * width + nx];
+                    if (nx, ny) == end && next_moves >= min_moves as usize {
+                        return next_heat;
+                    }
+                    buckets[next_heat as usize % ring].push(index);
+                    pending += 1;
+                }
+            }
+            heat += 1;
+        }
+    }
+}
+
+/// Optimal paths, with pluggable movement rules, by Dijkstra or A*
+pub mod solver {
+    use std::{
+        cmp::Reverse,
+        collections::{hash_map::Entry, BinaryHeap, HashMap},
+    };
+
+    use super::*;
+
+    /// What a crucible may do next, after `moves` tiles in a straight line
+    pub trait Movement {
+        fn can_go_straight(&self, moves: u8) -> bool;
+        fn can_turn(&self, moves: u8) -> bool;
+        fn can_reverse(&self, _moves: u8) -> bool {
+            false
+        }
+        /// Heat lost on top of the tile entered, for every quarter turn
+        fn turn_cost(&self) -> u32 {
+            0
+        }
+        fn can_stop(&self, moves: u8) -> bool;
+        /// Upper bound of `moves`
+        fn max_moves(&self) -> u8;
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct Rules {
+        pub min_moves: u8,
+        pub max_moves: u8,
+        pub reverse: bool,
+        pub turn_cost: u32,
+    }
+    impl Rules {
+        pub const CRUCIBLE: Self = Self {
+            min_moves: 1,
+            max_moves: 3,
+            reverse: false,
+            turn_cost: 0,
+        };
+        pub const ULTRA_CRUCIBLE: Self = Self {
+            min_moves: 4,
+            max_moves: 10,
+            reverse: false,
+            turn_cost: 0,
+        };
+    }
+    impl Movement for Rules {
+        fn can_go_straight(&self, moves: u8) -> bool {
+            moves < self.max_moves
+        }
+        fn can_turn(&self, moves: u8) -> bool {
+            moves >= self.min_moves
+        }
+        fn can_reverse(&self, moves: u8) -> bool {
+            self.reverse && moves >= self.min_moves
+        }
+        fn turn_cost(&self) -> u32 {
+            self.turn_cost
+        }
+        fn can_stop(&self, moves: u8) -> bool {
+            moves >= self.min_moves
+        }
+        fn max_moves(&self) -> u8 {
+            self.max_moves
+        }
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum Search {
+        Dijkstra,
+        /// Manhattan distance to the end, times the smallest heat of the map
+        AStar,
+    }
+
+    /// A tile entered by the crucible, and the direction it entered from
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct Step {
+        pub position: (usize, usize),
+        pub direction: Direction,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct Path {
+        pub heat: u32,
+        /// Every tile entered from the top left corner, which is not part of the path
+        pub steps: Vec<Step>,
+    }
+
+    /// `moves == 0` only at the start, where the crucible may go in any direction
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+    struct State {
+        x: usize,
+        y: usize,
+        direction: Direction,
+        moves: u8,
+    }
+
+    pub fn solve(heatmap: &[Vec<u32>], rules: &impl Movement, search: Search) -> Option<Path> {
+        let height = heatmap.len();
+        let width = heatmap.first()?.len();
+        let end = (width - 1, height - 1);
+        let min_heat = heatmap.iter().flatten().min().copied().unwrap_or(0);
+        let heuristic = |state: &State| match search {
+            Search::Dijkstra => 0,
+            Search::AStar => ((end.0 - state.x) + (end.1 - state.y)) as u32 * min_heat,
+        };
+
+        let start = State {
+            x: 0,
+            y: 0,
+            direction: Direction::East,
+            moves: 0,
+        };
+        // Heat lost to reach each state, and the previous state
+        let mut best = HashMap::from([(start, (0, None))]);
+        let mut queue = BinaryHeap::from([Reverse((heuristic(&start), 0, start))]);
+        while let Some(Reverse((_, heat, state))) = queue.pop() {
+            if best[&state].0 < heat {
+                continue;
+            }
+            if (state.x, state.y) == end && state.moves != 0 && rules.can_stop(state.moves) {
+                return Some(reconstruct(&best, state, heat));
+            }
+            for (next, cost) in next_states(state, rules, width, height) {
+                let heat = heat + cost + heatmap[next.y][next.x];
+                match best.entry(next) {
+                    Entry::Occupied(entry) if entry.get().0 <= heat => continue,
+                    Entry::Occupied(mut entry) => *entry.get_mut() = (heat, Some(state)),
+                    Entry::Vacant(entry) => {
+                        entry.insert((heat, Some(state)));
+                    }
+                }
+                queue.push(Reverse((heat + heuristic(&next), heat, next)));
+            }
+        }
+        None
+    }
+
+    /// States after moving one tile, with the turn costs
+    fn next_states(
+        state: State,
+        rules: &impl Movement,
+        width: usize,
+        height: usize,
+    ) -> impl Iterator<Item = (State, u32)> {
+        let quarter = rules.turn_cost();
+        let choices = if state.moves == 0 {
+            [
+                Some((Direction::North, 0)),
+                Some((Direction::East, 0)),
+                Some((Direction::South, 0)),
+                Some((Direction::West, 0)),
+            ]
+        } else {
+            let turn = rules.can_turn(state.moves);
+            [
+                rules
+                    .can_go_straight(state.moves)
+                    .then_some((state.direction, 0)),
+                turn.then_some((state.direction.turn_left(), quarter)),
+                turn.then_some((state.direction.turn_right(), quarter)),
+                rules
+                    .can_reverse(state.moves)
+                    .then_some((state.direction.turn_left().turn_left(), 2 * quarter)),
+            ]
+        };
+        choices
+            .into_iter()
+            .flatten()
+            .filter_map(move |(direction, cost)| {
+                let (x, y) = match direction {
+                    Direction::North => (state.x, state.y.checked_sub(1)?),
+                    Direction::East => (state.x + 1, state.y),
+                    Direction::South => (state.x, state.y + 1),
+                    Direction::West => (state.x.checked_sub(1)?, state.y),
+                };
+                if x >= width || y >= height {
+                    return None;
+                }
+                let moves = match direction == state.direction && state.moves != 0 {
+                    true => state.moves + 1,
+                    false => 1,
+                };
+                Some((
+                    State {
+                        x,
+                        y,
+                        direction,
+                        moves,
+                    },
+                    cost,
+                ))
+            })
+    }
+
+    fn reconstruct(best: &HashMap<State, (u32, Option<State>)>, end: State, heat: u32) -> Path {
+        let mut steps = Vec::new();
+        let mut current = end;
+        while let (_, Some(previous)) = best[&current] {
+            steps.push(Step {
+                position: (current.x, current.y),
+                direction: current.direction,
+            });
+            current = previous;
+        }
+        steps.reverse();
+        Path { heat, steps }
+    }
+
+    /// The heatmap with the path drawn over it, as arrows pointing where the crucible went
+    pub fn render(heatmap: &[Vec<u32>], path: &Path) -> String {
+        let mut lines = heatmap
+            .iter()
+            .map(|line| {
+                line.iter()
+                    .map(|heat| char::from_digit(*heat, 10).unwrap_or('?'))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        for step in &path.steps {
+            let (x, y) = step.position;
+            lines[y][x] = match step.direction {
+                Direction::North => '^',
+                Direction::East => '>',
+                Direction::South => 'v',
+                Direction::West => '<',
+            };
+        }
+        lines
+            .into_iter()
+            .map(|line| line.into_iter().chain(['\n']).collect::<String>())
+            .collect()
+    }
+}
+
+/// Random heatmaps of any size : heat losses from 1 to 9, reproducible from the seed
+pub mod generator {
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    pub fn input(seed: u64, width: usize, height: usize) -> String {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut input = String::with_capacity((width + 1) * height);
+        for _ in 0..height {
+            input.extend((0..width).map(|_| char::from(b'0' + rng.gen_range(1..=9))));
+            input.push('\n');
+        }
+        input
+    }
+}
+
 pub mod parsers {
     pub fn heat_map(input: &str) -> Vec<Vec<u32>> {
         let mut heatmap = Vec::new();
@@ -304,4 +615,161 @@ mod tests {
         assert_eq!(1037, dijkstra(&heatmap, 4, 10));
         // Day::test_part2(94)
     }
+
+    #[test]
+    fn bucket_dijkstra() {
+        let heatmap = parsers::heat_map(Day::SAMPLE_PART1);
+        assert_eq!(102, bucket::dijkstra(&heatmap, 1, 3));
+        assert_eq!(94, bucket::dijkstra(&heatmap, 4, 10));
+
+        let heatmap = parsers::heat_map(Day::INPUT);
+        assert_eq!(861, bucket::dijkstra(&heatmap, 1, 3));
+        assert_eq!(1037, bucket::dijkstra(&heatmap, 4, 10));
+
+        // Larger than the u8 coordinates of the other variants
+        let heatmap = parsers::heat_map(&generator::input(39, 300, 270));
+        let expected = solver::solve(&heatmap, &solver::Rules::CRUCIBLE, solver::Search::AStar);
+        assert_eq!(expected.unwrap().heat, bucket::dijkstra(&heatmap, 1, 3));
+    }
+
+    #[test]
+    fn test_solver() {
+        use solver::{Rules, Search};
+
+        let heatmap = parsers::heat_map(Day::SAMPLE_PART1);
+        for search in [Search::Dijkstra, Search::AStar] {
+            let path = solver::solve(&heatmap, &Rules::CRUCIBLE, search).unwrap();
+            assert_eq!(path.heat, 102);
+            let heat = path
+                .steps
+                .iter()
+                .map(|step| heatmap[step.position.1][step.position.0])
+                .sum::<u32>();
+            assert_eq!(heat, 102);
+            assert_eq!(path.steps.last().unwrap().position, (12, 12));
+
+            let path = solver::solve(&heatmap, &Rules::ULTRA_CRUCIBLE, search).unwrap();
+            assert_eq!(path.heat, 94);
+        }
+
+        let path = solver::solve(&heatmap, &Rules::CRUCIBLE, Search::Dijkstra).unwrap();
+        let render = solver::render(&heatmap, &path);
+        assert_eq!(render.lines().count(), 13);
+        assert_eq!(
+            render.matches(['^', '>', 'v', '<']).count(),
+            path.steps.len()
+        );
+        assert!(render.starts_with("2>"));
+    }
+
+    #[test]
+    fn test_movement_rules() {
+        use solver::{Rules, Search};
+
+        let heatmap = vec![vec![1; 3]; 3];
+        let rules = Rules {
+            turn_cost: 10,
+            ..Rules::CRUCIBLE
+        };
+        // Going right then down, with a single turn
+        let path = solver::solve(&heatmap, &rules, Search::AStar).unwrap();
+        assert_eq!(path.heat, 14);
+
+        // Too far to go straight : go back and forth to reset the moves
+        let heatmap = vec![vec![1; 4]];
+        let rules = Rules {
+            max_moves: 2,
+            ..Rules::CRUCIBLE
+        };
+        assert_eq!(solver::solve(&heatmap, &rules, Search::Dijkstra), None);
+        let reverse = Rules {
+            reverse: true,
+            ..rules
+        };
+        let path = solver::solve(&heatmap, &reverse, Search::Dijkstra).unwrap();
+        assert_eq!(path.heat, 5);
+        let positions = path
+            .steps
+            .iter()
+            .map(|step| step.position.0)
+            .collect::<Vec<_>>();
+        assert_eq!(positions, [1, 2, 1, 2, 3]);
+        // Reversing is two quarter turns
+        let reverse = Rules {
+            turn_cost: 3,
+            ..reverse
+        };
+        assert_eq!(
+            solver::solve(&heatmap, &reverse, Search::AStar)
+                .unwrap()
+                .heat,
+            17
+        );
+    }
+
+    #[test]
+    fn test_generator() {
+        let input = generator::input(17, 100, 50);
+        assert_eq!(input, generator::input(17, 100, 50));
+
+        let heatmap = parsers::heat_map(&input);
+        assert_eq!(50, heatmap.len());
+        assert!(heatmap.iter().all(|line| line.len() == 100));
+        assert_eq!(
+            Day::part1(&input),
+            using_pathfinding::dijkstra(&heatmap, 1, 3)
+        );
+    }
+
+    mod proptests {
+        use super::*;
+        use proptest::prelude::*;
+
+        /// Random heatmaps, large enough for the ultra crucible to reach the end
+        fn heatmap() -> impl Strategy<Value = Vec<Vec<u32>>> {
+            (5..=25_usize, 5..=25_usize).prop_flat_map(|(width, height)| {
+                prop::collection::vec(prop::collection::vec(1..=9_u32, width), height)
+            })
+        }
+
+        proptest! {
+            #[test]
+            fn part1_variants_agree(heatmap in heatmap()) {
+                let expected = dijkstra(&heatmap, 1, 3);
+                prop_assert_eq!(expected, bucket::dijkstra(&heatmap, 1, 3));
+                prop_assert_eq!(expected, using_pathfinding::dijkstra(&heatmap, 1, 3));
+                prop_assert_eq!(expected, using_pathfinding::astar(&heatmap, 1, 3));
+            }
+
+            #[test]
+            fn part2_variants_agree(heatmap in heatmap()) {
+                let expected = dijkstra(&heatmap, 4, 10);
+                prop_assert_eq!(expected, bucket::dijkstra(&heatmap, 4, 10));
+                prop_assert_eq!(expected, using_pathfinding::dijkstra(&heatmap, 4, 10));
+                prop_assert_eq!(expected, using_pathfinding::astar(&heatmap, 4, 10));
+            }
+        }
+
+        proptest! {
+            // Six searches per heatmap : fewer cases keep the debug test run short
+            #![proptest_config(ProptestConfig::with_cases(64))]
+
+            #[test]
+            fn solver_searches_agree(heatmap in heatmap()) {
+                use solver::{Rules, Search};
+                for (rules, expected) in [
+                    (Rules::CRUCIBLE, dijkstra(&heatmap, 1, 3)),
+                    (Rules::ULTRA_CRUCIBLE, dijkstra(&heatmap, 4, 10)),
+                ] {
+                    for search in [Search::Dijkstra, Search::AStar] {
+                        let path = solver::solve(&heatmap, &rules, search).unwrap();
+                        prop_assert_eq!(path.heat, expected);
+                    }
+                    let reverse = Rules { reverse: true, ..rules };
+                    let path = solver::solve(&heatmap, &reverse, Search::AStar).unwrap();
+                    prop_assert!(path.heat <= expected);
+                }
+            }
+        }
+    }
 }
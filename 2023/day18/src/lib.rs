@@ -1,5 +1,6 @@
 use aoc::Aoc;
 use itertools::Itertools;
+pub use polygon::{Containment, Polygon};
 
 pub struct Day;
 
@@ -12,80 +13,17 @@ impl Aoc for Day {
 
     fn part1(input: &str) -> Self::OUTPUT {
         let edges = parsers::part1(input);
-
-        let (mut x, mut y) = (0, 0);
-        let (mut xmin, mut xmax, mut ymin, mut ymax) = (0, 0, 0, 0);
-        for edge in &edges {
-            (x, y) = edge.direction.next_coordinates((x, y), edge.amount as i64);
-            xmin = xmin.min(x);
-            xmax = xmax.max(x);
-            ymin = ymin.min(y);
-            ymax = ymax.max(y);
-        }
-
-        let width = xmax - xmin;
-        let height = ymax - ymin;
-        let mut grid = (0..=height)
-            .map(|_| (0..=width).map(|_| Option::<Dig>::None).collect::<Vec<_>>())
-            .collect::<Vec<_>>();
-        let (mut x, mut y) = (-xmin, -ymin);
-        for (edge, next_edge) in edges.iter().circular_tuple_windows() {
-            for _ in 1..edge.amount {
-                (x, y) = edge.direction.next_coordinates((x, y), 1);
-                grid[y as usize][x as usize] = Some(Dig::from(edge.direction))
-            }
-            (x, y) = edge.direction.next_coordinates((x, y), 1);
-            grid[y as usize][x as usize] = Some(Dig::from((edge.direction, next_edge.direction)))
-        }
-
-        let mut count = 0;
-        for line in &grid {
-            let mut inside = false;
-            for square in line {
-                match square {
-                    None => {
-                        if inside {
-                            count += 1;
-                        }
-                    }
-                    Some(dig) => {
-                        count += 1;
-                        match dig {
-                            Dig::Vertical | Dig::CornerNW | Dig::CornerNE => inside = !inside,
-                            Dig::Horizontal | Dig::CornerSW | Dig::CornerSE => (),
-                        }
-                    }
-                }
-            }
-        }
-
-        count
+        Polygon::from_edges(&edges).lagoon()
     }
 
     fn part2(input: &str) -> Self::OUTPUT {
         let edges = parsers::part2(input);
-
-        let (mut x, mut y) = (0, 0);
-        let coords = std::iter::once((0, 0))
-            .chain(edges.iter().map(|edge| {
-                (x, y) = edge.direction.next_coordinates((x, y), edge.amount as i64);
-                (x, y)
-            }))
-            .collect::<Vec<_>>();
-
-        let area = coords
-            .iter()
-            .circular_tuple_windows()
-            .map(|((x1, y1), (x2, y2))| (y1 + y2) * (x1 - x2))
-            .sum::<i64>()
-            / 2;
-        let perimeter = edges.iter().map(|edge| edge.amount).sum::<u32>();
-        area.unsigned_abs() + (perimeter as u64 / 2) + 1
+        Polygon::from_edges(&edges).lagoon()
     }
 }
 
-#[derive(Debug, Clone, Copy)]
-enum Direction {
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
     Up,
     Down,
     Left,
@@ -104,7 +42,7 @@ impl TryFrom<char> for Direction {
     }
 }
 impl Direction {
-    fn next_coordinates(self, (x, y): (i64, i64), amount: i64) -> (i64, i64) {
+    pub fn next_coordinates(self, (x, y): (i64, i64), amount: i64) -> (i64, i64) {
         match self {
             Direction::Up => (x, y - amount),
             Direction::Down => (x, y + amount),
@@ -124,49 +62,341 @@ impl Direction {
     }
 }
 
-struct Edge {
-    direction: Direction,
-    amount: u32,
+#[derive(Debug, Clone, Copy)]
+pub struct Edge {
+    pub direction: Direction,
+    pub amount: u32,
 }
 
-#[derive(Debug, Clone)]
-enum Dig {
-    Vertical,
-    Horizontal,
-    CornerNE,
-    CornerNW,
-    CornerSE,
-    CornerSW,
+/// Exact integer geometry on the polygon dug by the edges, each vertex being the center of a square
+pub mod polygon {
+    use super::*;
+
+    #[derive(Debug, PartialEq, Eq, Clone, Copy)]
+    pub enum Containment {
+        Inside,
+        /// On the trench
+        Boundary,
+        Outside,
+    }
+
+    #[derive(Debug, Clone)]
+    pub struct Polygon {
+        vertices: Vec<(i64, i64)>,
+    }
+    impl Polygon {
+        /// Starts digging from (0, 0)
+        pub fn from_edges(edges: &[Edge]) -> Self {
+            let mut position = (0, 0);
+            let mut vertices = vec![position];
+            for edge in edges {
+                position = edge
+                    .direction
+                    .next_coordinates(position, edge.amount as i64);
+                vertices.push(position);
+            }
+            if vertices.len() > 1 && vertices.last() == vertices.first() {
+                vertices.pop();
+            }
+            Self { vertices }
+        }
+
+        pub fn vertices(&self) -> &[(i64, i64)] {
+            &self.vertices
+        }
+
+        /// The last edge comes back to the first vertex
+        pub fn segments(&self) -> impl Iterator<Item = ((i64, i64), (i64, i64))> + '_ {
+            self.vertices.iter().copied().circular_tuple_windows()
+        }
+
+        /// Twice the area, by the shoelace formula.
+        /// Positive when the edges turn clockwise on screen (y pointing down)
+        pub fn double_signed_area(&self) -> i64 {
+            self.segments()
+                .map(|((x1, y1), (x2, y2))| x1 * y2 - x2 * y1)
+                .sum()
+        }
+
+        /// Area of the polygon going through the center of the boundary squares
+        pub fn area(&self) -> u64 {
+            self.double_signed_area().unsigned_abs() / 2
+        }
+
+        /// Number of squares dug along the trench
+        pub fn boundary_count(&self) -> u64 {
+            self.segments()
+                .map(|((x1, y1), (x2, y2))| x1.abs_diff(x2) + y1.abs_diff(y2))
+                .sum()
+        }
+
+        /// Number of squares inside the trench, by Pick's theorem : A = I + B/2 - 1
+        pub fn interior_count(&self) -> u64 {
+            self.area() + 1 - self.boundary_count() / 2
+        }
+
+        /// Total number of squares dug, trench and interior
+        pub fn lagoon(&self) -> u64 {
+            self.interior_count() + self.boundary_count()
+        }
+
+        /// Pairs of segments (by index) which touch or cross each other,
+        /// apart from the shared corner of consecutive segments
+        pub fn self_intersections(&self) -> Vec<(usize, usize)> {
+            let segments = self.segments().collect::<Vec<_>>();
+            let count = segments.len();
+            let mut intersections = Vec::new();
+            for i in 0..count {
+                for j in i + 1..count {
+                    let (a, b) = (segments[i], segments[j]);
+                    let consecutive = j == i + 1 || (i == 0 && j == count - 1);
+                    let touching = if consecutive {
+                        // Going back over the previous segment
+                        let (first, second) = if j == i + 1 { (a, b) } else { (b, a) };
+                        Self::backtracks(first, second)
+                    } else {
+                        Self::overlaps(a, b)
+                    };
+                    if touching {
+                        intersections.push((i, j));
+                    }
+                }
+            }
+            intersections
+        }
+
+        pub fn is_simple(&self) -> bool {
+            self.self_intersections().is_empty()
+        }
+
+        /// Axis aligned segments are their own bounding boxes
+        fn overlaps(((ax1, ay1), (ax2, ay2)): Segment, ((bx1, by1), (bx2, by2)): Segment) -> bool {
+            ax1.min(ax2) <= bx1.max(bx2)
+                && bx1.min(bx2) <= ax1.max(ax2)
+                && ay1.min(ay2) <= by1.max(by2)
+                && by1.min(by2) <= ay1.max(ay2)
+        }
+
+        fn backtracks(((x1, y1), (x2, y2)): Segment, (_, (x3, y3)): Segment) -> bool {
+            let first = ((x2 - x1).signum(), (y2 - y1).signum());
+            let second = ((x3 - x2).signum(), (y3 - y2).signum());
+            first == (-second.0, -second.1)
+        }
+
+        /// Ray casting towards +x, only the vertical segments can be crossed.
+        /// Each crossing counts the segment from its lower end included to its upper end excluded
+        pub fn contains(&self, (x, y): (i64, i64)) -> Containment {
+            let on_boundary = self
+                .segments()
+                .any(|segment| Self::overlaps(segment, ((x, y), (x, y))));
+            if on_boundary {
+                return Containment::Boundary;
+            }
+            let crossings = self
+                .segments()
+                .filter(|((x1, y1), (x2, y2))| {
+                    x1 == x2 && *x1 > x && (*y1.min(y2)..*y1.max(y2)).contains(&y)
+                })
+                .count();
+            match crossings % 2 {
+                1 => Containment::Inside,
+                _ => Containment::Outside,
+            }
+        }
+    }
+
+    type Segment = ((i64, i64), (i64, i64));
 }
-impl From<Direction> for Dig {
-    fn from(value: Direction) -> Self {
-        match value {
-            Direction::Up | Direction::Down => Dig::Vertical,
-            Direction::Left | Direction::Right => Dig::Horizontal,
+
+/// Renders the part 1 trench, to check the polygon orientation and its interior visually.
+/// `cargo run -p day18 -- svg > lagoon.svg`
+pub mod svg {
+    use std::fmt::Write;
+
+    use super::*;
+
+    pub fn part1(input: &str) -> String {
+        render(&parsers::colours(input))
+    }
+
+    /// Each square is one unit wide, centered on the polygon vertices.
+    /// The lagoon is shaded, each segment of the trench is drawn in its colour,
+    /// and the start is marked with an arrow showing the digging direction
+    pub fn render(edges: &[(Edge, u32)]) -> String {
+        let polygon = Polygon::from_edges(&edges.iter().map(|(edge, _)| *edge).collect::<Vec<_>>());
+        let vertices = polygon.vertices();
+        let xmin = vertices.iter().map(|(x, _)| *x).min().unwrap_or(0) - 1;
+        let xmax = vertices.iter().map(|(x, _)| *x).max().unwrap_or(0) + 1;
+        let ymin = vertices.iter().map(|(_, y)| *y).min().unwrap_or(0) - 1;
+        let ymax = vertices.iter().map(|(_, y)| *y).max().unwrap_or(0) + 1;
+
+        let mut svg = String::new();
+        writeln!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="{xmin} {ymin} {} {}">"#,
+            xmax - xmin,
+            ymax - ymin
+        )
+        .unwrap();
+        svg.push_str(concat!(
+            r#"  <defs><marker id="arrow" viewBox="0 0 2 2" refX="1" refY="1" markerWidth="2" markerHeight="2" orient="auto">"#,
+            r#"<path d="M 0 0 L 2 1 L 0 2 z"/></marker></defs>"#,
+            "\n"
+        ));
+        let points = vertices.iter().map(|(x, y)| format!("{x},{y}")).join(" ");
+        writeln!(
+            svg,
+            r##"  <polygon points="{points}" fill="#808080" fill-opacity="0.5" stroke="none"/>"##
+        )
+        .unwrap();
+
+        let mut position = (0, 0);
+        for (index, (edge, colour)) in edges.iter().enumerate() {
+            let (x1, y1) = position;
+            position = edge
+                .direction
+                .next_coordinates(position, edge.amount as i64);
+            let (x2, y2) = position;
+            let marker = match index {
+                0 => r#" marker-mid="url(#arrow)""#,
+                _ => "",
+            };
+            // A middle point for the start arrow
+            let (xm, ym) = ((x1 + x2) as f64 / 2.0, (y1 + y2) as f64 / 2.0);
+            writeln!(
+                svg,
+                r##"  <polyline points="{x1},{y1} {xm},{ym} {x2},{y2}" stroke="#{colour:06x}" stroke-width="1" stroke-linecap="square" fill="none"{marker}/>"##
+            )
+            .unwrap();
         }
+        svg.push_str("</svg>\n");
+        svg
     }
 }
-impl From<(Direction, Direction)> for Dig {
-    fn from((previous, next): (Direction, Direction)) -> Self {
-        match (previous, next) {
-            (Direction::Up, Direction::Left) | (Direction::Right, Direction::Down) => Dig::CornerSW,
-            (Direction::Up, Direction::Right) | (Direction::Left, Direction::Down) => Dig::CornerSE,
-            (Direction::Down, Direction::Left) | (Direction::Right, Direction::Up) => Dig::CornerNW,
-            (Direction::Down, Direction::Right) | (Direction::Left, Direction::Up) => Dig::CornerNE,
-            (Direction::Up, Direction::Up) | (Direction::Down, Direction::Down) => Dig::Vertical,
-            (Direction::Left, Direction::Left) | (Direction::Right, Direction::Right) => {
-                Dig::Horizontal
+
+/// The original part 1 : dig the trench on a grid and scan the lines.
+/// Only used to check the polygon
+#[cfg(test)]
+mod raster {
+    use super::*;
+
+    /// Dug squares of every line, and the coordinates of the top left corner
+    pub fn dig(edges: &[Edge]) -> (Vec<Vec<Option<Dig>>>, (i64, i64)) {
+        let (mut x, mut y) = (0, 0);
+        let (mut xmin, mut xmax, mut ymin, mut ymax) = (0, 0, 0, 0);
+        for edge in edges {
+            (x, y) = edge.direction.next_coordinates((x, y), edge.amount as i64);
+            xmin = xmin.min(x);
+            xmax = xmax.max(x);
+            ymin = ymin.min(y);
+            ymax = ymax.max(y);
+        }
+
+        let width = xmax - xmin;
+        let height = ymax - ymin;
+        let mut grid = (0..=height)
+            .map(|_| (0..=width).map(|_| Option::<Dig>::None).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        let (mut x, mut y) = (-xmin, -ymin);
+        for (edge, next_edge) in edges.iter().circular_tuple_windows() {
+            for _ in 1..edge.amount {
+                (x, y) = edge.direction.next_coordinates((x, y), 1);
+                grid[y as usize][x as usize] = Some(Dig::from(edge.direction))
             }
-            (Direction::Up, Direction::Down)
-            | (Direction::Down, Direction::Up)
-            | (Direction::Left, Direction::Right)
-            | (Direction::Right, Direction::Left) => {
-                panic!("invalid sequence of directions {:?} {:?}", previous, next)
+            (x, y) = edge.direction.next_coordinates((x, y), 1);
+            grid[y as usize][x as usize] = Some(Dig::from((edge.direction, next_edge.direction)))
+        }
+        (grid, (xmin, ymin))
+    }
+
+    /// Inside state of every square, scanning lines from the left
+    pub fn inside(grid: &[Vec<Option<Dig>>]) -> Vec<Vec<bool>> {
+        grid.iter()
+            .map(|line| {
+                let mut inside = false;
+                line.iter()
+                    .map(|square| match square {
+                        None => inside,
+                        Some(dig) => {
+                            match dig {
+                                Dig::Vertical | Dig::CornerNW | Dig::CornerNE => inside = !inside,
+                                Dig::Horizontal | Dig::CornerSW | Dig::CornerSE => (),
+                            }
+                            false
+                        }
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    pub fn lagoon(edges: &[Edge]) -> u64 {
+        let (grid, _) = dig(edges);
+        let inside = inside(&grid);
+        grid.iter()
+            .flatten()
+            .zip(inside.iter().flatten())
+            .filter(|(dig, inside)| dig.is_some() || **inside)
+            .count() as u64
+    }
+
+    #[derive(Debug, Clone)]
+    pub enum Dig {
+        Vertical,
+        Horizontal,
+        CornerNE,
+        CornerNW,
+        CornerSE,
+        CornerSW,
+    }
+    impl From<Direction> for Dig {
+        fn from(value: Direction) -> Self {
+            match value {
+                Direction::Up | Direction::Down => Dig::Vertical,
+                Direction::Left | Direction::Right => Dig::Horizontal,
+            }
+        }
+    }
+    impl From<(Direction, Direction)> for Dig {
+        fn from((previous, next): (Direction, Direction)) -> Self {
+            match (previous, next) {
+                (Direction::Up, Direction::Left) | (Direction::Right, Direction::Down) => {
+                    Dig::CornerSW
+                }
+                (Direction::Up, Direction::Right) | (Direction::Left, Direction::Down) => {
+                    Dig::CornerSE
+                }
+                (Direction::Down, Direction::Left) | (Direction::Right, Direction::Up) => {
+                    Dig::CornerNW
+                }
+                (Direction::Down, Direction::Right) | (Direction::Left, Direction::Up) => {
+                    Dig::CornerNE
+                }
+                (Direction::Up, Direction::Up) | (Direction::Down, Direction::Down) => {
+                    Dig::Vertical
+                }
+                (Direction::Left, Direction::Left) | (Direction::Right, Direction::Right) => {
+                    Dig::Horizontal
+                }
+                (Direction::Up, Direction::Down)
+                | (Direction::Down, Direction::Up)
+                | (Direction::Left, Direction::Right)
+                | (Direction::Right, Direction::Left) => {
+                    panic!("invalid sequence of directions {:?} {:?}", previous, next)
+                }
             }
         }
     }
 }
 
+/// Part 2 decodes the edges from the colour codes
+#[cfg(feature = "fuzzing")]
+pub fn fuzz_parsers(input: &str) {
+    let _ = parsers::edges_part1(input);
+    let _ = parsers::edges_part2(input);
+    let _ = parsers::coloured_edges(input);
+}
+
 mod parsers {
     use nom::{
         bytes::complete::{tag, take, take_until, take_until1},
@@ -207,12 +437,51 @@ mod parsers {
         Ok((input, edge))
     }
 
+    /// The `(#rrggbb)` colour of the part 1 instructions
+    fn colour(input: &str) -> IResult<&str, u32> {
+        let (input, _) = space1(input)?;
+        let (input, _) = tag("(#")(input)?;
+        let (input, colour) = take(6_usize)
+            .map_res(|s| u32::from_str_radix(s, 16))
+            .parse(input)?;
+        let (input, _) = tag(")")(input)?;
+        Ok((input, colour))
+    }
+
+    fn coloured_edge(input: &str) -> IResult<&str, (Edge, u32)> {
+        let (input, direction) = direction(input)?;
+        let (input, _) = space1(input)?;
+        let (input, amount) = u32(input)?;
+        let (input, colour) = colour(input)?;
+
+        let edge = Edge { direction, amount };
+        Ok((input, (edge, colour)))
+    }
+
+    pub fn coloured_edges(input: &str) -> IResult<&str, Vec<(Edge, u32)>> {
+        separated_list1(line_ending, coloured_edge)(input)
+    }
+
+    pub fn edges_part1(input: &str) -> IResult<&str, Vec<Edge>> {
+        separated_list1(line_ending, edge_part1)(input)
+    }
+
+    pub fn edges_part2(input: &str) -> IResult<&str, Vec<Edge>> {
+        separated_list1(line_ending, edge_part2)(input)
+    }
+
     pub fn part1(input: &str) -> Vec<Edge> {
-        separated_list1(line_ending, edge_part1)(input).unwrap().1
+        edges_part1(input).unwrap().1
     }
 
     pub fn part2(input: &str) -> Vec<Edge> {
-        separated_list1(line_ending, edge_part2)(input).unwrap().1
+        edges_part2(input).unwrap().1
+    }
+
+    pub fn colours(input: &str) -> Vec<(Edge, u32)> {
+        coloured_edges(input)
+            .unwrap_or_else(|e| panic!("Parser failed {e:?}"))
+            .1
     }
 }
 
@@ -229,4 +498,63 @@ mod tests {
     fn test_part2() {
         Day::test_part2(952408144115)
     }
+
+    #[test]
+    fn test_polygon() {
+        let edges = parsers::part1(Day::SAMPLE_PART1);
+        let polygon = Polygon::from_edges(&edges);
+        assert_eq!(polygon.boundary_count(), 38);
+        assert_eq!(polygon.area(), 42);
+        assert_eq!(polygon.interior_count(), 24);
+        assert!(polygon.is_simple());
+
+        // Checks one square out of `stride` on both axes
+        for (input, stride) in [(Day::SAMPLE_PART1, 1), (Day::INPUT, 7)] {
+            let edges = parsers::part1(input);
+            let polygon = Polygon::from_edges(&edges);
+            assert_eq!(polygon.lagoon(), raster::lagoon(&edges));
+            assert!(polygon.is_simple());
+
+            let (grid, (xmin, ymin)) = raster::dig(&edges);
+            let inside = raster::inside(&grid);
+            for (y, line) in grid.iter().enumerate().step_by(stride) {
+                for (x, dig) in line.iter().enumerate().step_by(stride) {
+                    let expected = match (dig, inside[y][x]) {
+                        (Some(_), _) => Containment::Boundary,
+                        (None, true) => Containment::Inside,
+                        (None, false) => Containment::Outside,
+                    };
+                    let point = (x as i64 + xmin, y as i64 + ymin);
+                    assert_eq!(polygon.contains(point), expected, "{point:?}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_svg() {
+        let svg = svg::part1(Day::SAMPLE_PART1);
+        assert!(svg.starts_with(r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="-1 -1 8 11">"#));
+        assert_eq!(svg.matches("<polyline").count(), 14);
+        assert!(svg.contains(
+            r##"<polyline points="0,0 3,0 6,0" stroke="#70c710" stroke-width="1" stroke-linecap="square" fill="none" marker-mid="url(#arrow)"/>"##
+        ));
+        assert!(svg.contains(
+            r#"<polygon points="0,0 6,0 6,5 4,5 4,7 6,7 6,9 1,9 1,7 0,7 0,5 2,5 2,2 0,2" "#
+        ));
+        assert!(svg.ends_with("</svg>\n"));
+    }
+
+    #[test]
+    fn test_self_intersections() {
+        let edges = parsers::part1("R 4 (#000000)\nD 2 (#000000)\nL 2 (#000000)\nU 4 (#000000)\nL 2 (#000000)\nD 2 (#000000)\n");
+        let polygon = Polygon::from_edges(&edges);
+        // Going up through the first segment
+        assert_eq!(polygon.self_intersections(), [(0, 3)]);
+
+        let edges = parsers::part1("R 2 (#000000)\nL 2 (#000000)\n");
+        let polygon = Polygon::from_edges(&edges);
+        // Going back over the previous segment
+        assert_eq!(polygon.self_intersections(), [(0, 1)]);
+    }
 }
@@ -2,5 +2,8 @@ use aoc::Aoc;
 use day18::Day;
 
 fn main() {
-    Day::main()
+    match std::env::args().nth(1).as_deref() {
+        Some("svg") => print!("{}", day18::svg::part1(Day::INPUT)),
+        _ => Day::main(),
+    }
 }
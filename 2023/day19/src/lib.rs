@@ -12,67 +12,39 @@ impl Aoc for Day {
     const SAMPLE_PART2: &'static str = include_str!("../inputs/sample2.txt");
 
     fn part1(input: &str) -> Self::OUTPUT {
-        let (workflows, pieces) = parsers::part1(input).unwrap().1;
-
-        let mut total = 0;
-        for piece in &pieces {
-            let mut current = "in";
-            loop {
-                let workflow = workflows.get(current).unwrap();
-                let next = workflow.process_piece(piece);
-                match next {
-                    "R" => break,
-                    "A" => {
-                        total += piece.value();
-                        break;
-                    }
-                    _ => current = next,
-                }
-            }
-        }
-
-        total
+        let (workflows, pieces) =
+            parsers::parse(input).unwrap_or_else(|e| panic!("Parser failed : {e}"));
+        let tree = DecisionTree::compile(&workflows).unwrap();
+        pieces
+            .iter()
+            .filter(|piece| tree.accepts(piece))
+            .map(Piece::value)
+            .sum()
     }
 
     fn part2(input: &str) -> Self::OUTPUT {
-        let (workflows, _) = parsers::part1(input).unwrap().1;
-        let mut ranges = vec![("in".to_string(), PieceRange::default())];
-        let mut count = 0;
-        while let Some((name, mut range)) = ranges.pop() {
-            match name.as_str() {
-                "R" => continue,
-                "A" => {
-                    count += range.count();
-                    continue;
-                }
-                _ => (),
-            }
-            let workflow = workflows.get(&name).unwrap();
-            for (condition, next) in &workflow.conditions {
-                if let Some(new_range) = range.split_condition(condition) {
-                    ranges.push((next.clone(), new_range));
-                }
-            }
-            ranges.push((workflow.default.clone(), range));
-        }
-
-        count as u64
+        let (workflows, _) =
+            parsers::parse(input).unwrap_or_else(|e| panic!("Parser failed : {e}"));
+        let tree = DecisionTree::compile(&workflows).unwrap();
+        tree.count_accepted(PieceRange::default())
     }
 }
 
-struct Piece {
-    x: u16,
-    m: u16,
-    a: u16,
-    s: u16,
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Piece {
+    pub x: u16,
+    pub m: u16,
+    pub a: u16,
+    pub s: u16,
 }
 
-#[derive(Debug, Clone)]
-struct PieceRange {
-    x: Range<u16>,
-    m: Range<u16>,
-    a: Range<u16>,
-    s: Range<u16>,
+/// Hyper-rectangle of pieces
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PieceRange {
+    pub x: Range<u16>,
+    pub m: Range<u16>,
+    pub a: Range<u16>,
+    pub s: Range<u16>,
 }
 impl Default for PieceRange {
     fn default() -> Self {
@@ -85,61 +57,131 @@ impl Default for PieceRange {
     }
 }
 impl PieceRange {
-    fn split_condition(&mut self, condition: &Condition) -> Option<Self> {
+    fn field(&self, xmas: Xmas) -> &Range<u16> {
+        match xmas {
+            Xmas::X => &self.x,
+            Xmas::M => &self.m,
+            Xmas::A => &self.a,
+            Xmas::S => &self.s,
+        }
+    }
+
+    fn with_field(&self, xmas: Xmas, range: Range<u16>) -> Self {
         let mut new = self.clone();
-        let (range, new_range) = match condition.xmas {
-            Xmas::X => (&mut self.x, &mut new.x),
-            Xmas::M => (&mut self.m, &mut new.m),
-            Xmas::A => (&mut self.a, &mut new.a),
-            Xmas::S => (&mut self.s, &mut new.s),
-        };
-        match condition.ord {
-            GreaterOrLesser::Greater => {
-                if range.end <= condition.value {
-                    return None;
-                }
-                if range.start <= condition.value {
-                    range.end = condition.value + 1;
-                    new_range.start = condition.value + 1;
-                } else {
-                    range.end = range.start;
-                }
-                Some(new)
-            }
-            GreaterOrLesser::Lesser => {
-                if range.start >= condition.value {
-                    return None;
+        match xmas {
+            Xmas::X => new.x = range,
+            Xmas::M => new.m = range,
+            Xmas::A => new.a = range,
+            Xmas::S => new.s = range,
+        }
+        new
+    }
+
+    /// Splits the range into the pieces matching the condition, and the others.
+    /// The ranges returned are never empty
+    fn split_condition(&self, condition: &Condition) -> (Vec<Self>, Vec<Self>) {
+        if self.count() == 0 {
+            return (Vec::new(), Vec::new());
+        }
+        match condition.operand {
+            Operand::Value(value) => self.split_value(condition.xmas, condition.comparison, value),
+            Operand::Field(other) => {
+                // Slices along the first field, merging the consecutive values
+                // for which the condition applies to the whole range of the other field
+                let mut matching = Vec::new();
+                let mut others = Vec::new();
+                let mut run: Option<(Range<u16>, bool)> = None;
+                for value in self.field(condition.xmas).clone() {
+                    let slice = self.with_field(condition.xmas, value..value + 1);
+                    let (m, o) = slice.split_value(other, condition.comparison.flipped(), value);
+                    let uniform = match (m.is_empty(), o.is_empty()) {
+                        (false, true) => Some(true),
+                        (true, false) => Some(false),
+                        _ => None,
+                    };
+                    match (&mut run, uniform) {
+                        (Some((range, kind)), Some(u)) if *kind == u => range.end = value + 1,
+                        _ => {
+                            if let Some((range, kind)) = run.take() {
+                                let merged = self.with_field(condition.xmas, range);
+                                match kind {
+                                    true => matching.push(merged),
+                                    false => others.push(merged),
+                                }
+                            }
+                            match uniform {
+                                Some(kind) => run = Some((value..value + 1, kind)),
+                                None => {
+                                    matching.extend(m);
+                                    others.extend(o);
+                                }
+                            }
+                        }
+                    }
                 }
-                if range.end >= condition.value {
-                    range.start = condition.value;
-                    new_range.end = condition.value;
-                } else {
-                    range.end = range.start;
+                if let Some((range, kind)) = run {
+                    let merged = self.with_field(condition.xmas, range);
+                    match kind {
+                        true => matching.push(merged),
+                        false => others.push(merged),
+                    }
                 }
-                Some(new)
+                (matching, others)
             }
         }
     }
 
-    fn count(&self) -> usize {
-        self.x.len() * self.m.len() * self.a.len() * self.s.len()
+    fn split_value(
+        &self,
+        xmas: Xmas,
+        comparison: Comparison,
+        value: u16,
+    ) -> (Vec<Self>, Vec<Self>) {
+        let range = self.field(xmas);
+        let slices = |comparison: Comparison| {
+            comparison
+                .intervals(value)
+                .into_iter()
+                .filter_map(|interval| {
+                    let start = interval.start.max(range.start as u32);
+                    let end = interval.end.min(range.end as u32);
+                    (start < end).then(|| self.with_field(xmas, start as u16..end as u16))
+                })
+                .collect::<Vec<_>>()
+        };
+        (slices(comparison), slices(comparison.negated()))
+    }
+
+    pub fn count(&self) -> u64 {
+        [&self.x, &self.m, &self.a, &self.s]
+            .into_iter()
+            .map(|range| range.len() as u64)
+            .product()
     }
 }
 
-struct WorkFlow {
+#[derive(Debug)]
+pub struct WorkFlow {
     conditions: Vec<(Condition, String)>,
     default: String,
 }
 
-#[derive(Debug)]
-struct Condition {
+/// Compares a field of the piece to a value, or to another field
+#[derive(Debug, Clone)]
+pub struct Condition {
     xmas: Xmas,
-    ord: GreaterOrLesser,
-    value: u16,
+    comparison: Comparison,
+    operand: Operand,
 }
 
-#[derive(Debug)]
-enum Xmas {
+#[derive(Debug, Clone, Copy)]
+pub enum Operand {
+    Value(u16),
+    Field(Xmas),
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Xmas {
     X,
     M,
     A,
@@ -158,65 +200,405 @@ impl FromStr for Xmas {
     }
 }
 
-#[derive(Debug)]
-enum GreaterOrLesser {
-    Greater,
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
     Lesser,
+    LesserOrEqual,
+    Greater,
+    GreaterOrEqual,
+    Equal,
+    NotEqual,
 }
-impl FromStr for GreaterOrLesser {
+impl FromStr for Comparison {
     type Err = ();
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
-            "<" => Ok(GreaterOrLesser::Lesser),
-            ">" => Ok(GreaterOrLesser::Greater),
+            "<" => Ok(Comparison::Lesser),
+            "<=" => Ok(Comparison::LesserOrEqual),
+            ">" => Ok(Comparison::Greater),
+            ">=" => Ok(Comparison::GreaterOrEqual),
+            "==" => Ok(Comparison::Equal),
+            "!=" => Ok(Comparison::NotEqual),
             _ => Err(()),
         }
     }
 }
+impl Comparison {
+    fn compare(self, left: u16, right: u16) -> bool {
+        match self {
+            Comparison::Lesser => left < right,
+            Comparison::LesserOrEqual => left <= right,
+            Comparison::Greater => left > right,
+            Comparison::GreaterOrEqual => left >= right,
+            Comparison::Equal => left == right,
+            Comparison::NotEqual => left != right,
+        }
+    }
 
-impl WorkFlow {
-    fn process_piece<'a>(&'a self, piece: &Piece) -> &'a str {
-        for (condition, destination) in &self.conditions {
-            if piece.check_condition(condition) {
-                return destination.as_str();
-            }
+    /// Matches exactly the values this comparison doesn't match
+    fn negated(self) -> Self {
+        match self {
+            Comparison::Lesser => Comparison::GreaterOrEqual,
+            Comparison::LesserOrEqual => Comparison::Greater,
+            Comparison::Greater => Comparison::LesserOrEqual,
+            Comparison::GreaterOrEqual => Comparison::Lesser,
+            Comparison::Equal => Comparison::NotEqual,
+            Comparison::NotEqual => Comparison::Equal,
+        }
+    }
+
+    /// Same comparison with the operands swapped : `a < b` is `b > a`
+    fn flipped(self) -> Self {
+        match self {
+            Comparison::Lesser => Comparison::Greater,
+            Comparison::LesserOrEqual => Comparison::GreaterOrEqual,
+            Comparison::Greater => Comparison::Lesser,
+            Comparison::GreaterOrEqual => Comparison::LesserOrEqual,
+            Comparison::Equal | Comparison::NotEqual => self,
+        }
+    }
+
+    /// Values matched when compared to `value`, as u32 to hold `u16::MAX + 1`.
+    /// Only `NotEqual` needs the second interval, it is empty otherwise
+    fn intervals(self, value: u16) -> [Range<u32>; 2] {
+        let value = value as u32;
+        let all = u16::MAX as u32 + 1;
+        match self {
+            Comparison::Lesser => [0..value, 0..0],
+            Comparison::LesserOrEqual => [0..value + 1, 0..0],
+            Comparison::Greater => [value + 1..all, 0..0],
+            Comparison::GreaterOrEqual => [value..all, 0..0],
+            Comparison::Equal => [value..value + 1, 0..0],
+            Comparison::NotEqual => [0..value, value + 1..all],
         }
-        &self.default
     }
 }
 
 impl Piece {
-    fn check_condition(&self, condition: &Condition) -> bool {
-        let number = match condition.xmas {
+    fn field(&self, xmas: Xmas) -> u16 {
+        match xmas {
             Xmas::X => self.x,
             Xmas::M => self.m,
             Xmas::A => self.a,
             Xmas::S => self.s,
-        };
-        match condition.ord {
-            GreaterOrLesser::Greater => number > condition.value,
-            GreaterOrLesser::Lesser => number < condition.value,
         }
     }
 
+    fn check_condition(&self, condition: &Condition) -> bool {
+        let right = match condition.operand {
+            Operand::Value(value) => value,
+            Operand::Field(xmas) => self.field(xmas),
+        };
+        condition
+            .comparison
+            .compare(self.field(condition.xmas), right)
+    }
+
     fn value(&self) -> u64 {
         self.x as u64 + self.m as u64 + self.a as u64 + self.s as u64
     }
 }
 
-mod parsers {
+pub use decision::{CompileError, DecisionTree, Target, UnreachableRule};
+
+/// Workflows compiled into a tree of binary decisions : every rule either sends a piece
+/// to its target or to the next rule. Workflows are referenced by index instead of by name.
+pub mod decision {
     use std::collections::HashMap;
 
+    use super::*;
+
+    pub type WorkflowId = usize;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum Target {
+        Accept,
+        Reject,
+        Workflow(WorkflowId),
+    }
+
+    #[derive(Debug)]
+    struct Node {
+        rules: Vec<(Condition, Target)>,
+        default: Target,
+    }
+
+    #[derive(Debug, PartialEq, Eq)]
+    pub enum CompileError {
+        /// There is no `in` workflow
+        MissingStart,
+        UndefinedWorkflow(String),
+        /// Workflows sending pieces to each other in a loop
+        Cycle(Vec<String>),
+    }
+
+    /// A rule which never applies to any piece. The default rule of a workflow comes after all its rules
+    #[derive(Debug, PartialEq, Eq)]
+    pub struct UnreachableRule {
+        pub workflow: String,
+        pub rule: usize,
+    }
+
+    #[derive(Debug)]
+    pub struct DecisionTree {
+        names: Vec<String>,
+        nodes: Vec<Node>,
+        start: WorkflowId,
+    }
+    impl DecisionTree {
+        pub fn compile(workflows: &HashMap<String, WorkFlow>) -> Result<Self, CompileError> {
+            let mut names = workflows.keys().cloned().collect::<Vec<_>>();
+            names.sort();
+            let ids = names
+                .iter()
+                .enumerate()
+                .map(|(id, name)| (name.as_str(), id))
+                .collect::<HashMap<_, _>>();
+            let target = |name: &String| match name.as_str() {
+                "A" => Ok(Target::Accept),
+                "R" => Ok(Target::Reject),
+                _ => ids
+                    .get(name.as_str())
+                    .map(|id| Target::Workflow(*id))
+                    .ok_or_else(|| CompileError::UndefinedWorkflow(name.clone())),
+            };
+            let nodes = names
+                .iter()
+                .map(|name| {
+                    let workflow = &workflows[name];
+                    let rules = workflow
+                        .conditions
+                        .iter()
+                        .map(|(condition, next)| Ok((condition.clone(), target(next)?)))
+                        .collect::<Result<Vec<_>, _>>()?;
+                    let default = target(&workflow.default)?;
+                    Ok(Node { rules, default })
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            let start = *ids.get("in").ok_or(CompileError::MissingStart)?;
+
+            let tree = Self {
+                names,
+                nodes,
+                start,
+            };
+            tree.check_cycles()?;
+            Ok(tree)
+        }
+
+        pub fn workflow_id(&self, name: &str) -> Option<WorkflowId> {
+            self.names.iter().position(|n| n == name)
+        }
+
+        pub fn workflow_name(&self, id: WorkflowId) -> &str {
+            &self.names[id]
+        }
+
+        fn targets(&self, id: WorkflowId) -> impl Iterator<Item = WorkflowId> + '_ {
+            let node = &self.nodes[id];
+            node.rules
+                .iter()
+                .map(|(_, target)| *target)
+                .chain([node.default])
+                .filter_map(|target| match target {
+                    Target::Workflow(id) => Some(id),
+                    _ => None,
+                })
+        }
+
+        /// Depth first search, a workflow found again in the current path closes a loop
+        fn check_cycles(&self) -> Result<(), CompileError> {
+            #[derive(Clone, Copy, PartialEq)]
+            enum State {
+                New,
+                InPath,
+                Done,
+            }
+            let mut states = vec![State::New; self.nodes.len()];
+            for root in 0..self.nodes.len() {
+                if states[root] != State::New {
+                    continue;
+                }
+                states[root] = State::InPath;
+                let mut path = vec![(root, self.targets(root))];
+                while let Some((id, targets)) = path.last_mut() {
+                    let id = *id;
+                    match targets.next() {
+                        Some(next) if states[next] == State::InPath => {
+                            let start = path.iter().position(|(id, _)| *id == next).unwrap();
+                            let cycle = path[start..]
+                                .iter()
+                                .map(|(id, _)| self.names[*id].clone())
+                                .collect();
+                            return Err(CompileError::Cycle(cycle));
+                        }
+                        Some(next) if states[next] == State::New => {
+                            states[next] = State::InPath;
+                            path.push((next, self.targets(next)));
+                        }
+                        Some(_) => (),
+                        None => {
+                            states[id] = State::Done;
+                            path.pop();
+                        }
+                    }
+                }
+            }
+            Ok(())
+        }
+
+        pub fn accepts(&self, piece: &Piece) -> bool {
+            let mut current = self.start;
+            loop {
+                let node = &self.nodes[current];
+                let target = node
+                    .rules
+                    .iter()
+                    .find(|(condition, _)| piece.check_condition(condition))
+                    .map_or(node.default, |(_, target)| *target);
+                match target {
+                    Target::Accept => return true,
+                    Target::Reject => return false,
+                    Target::Workflow(next) => current = next,
+                }
+            }
+        }
+
+        /// Splits the range into disjoint hyper-rectangles, lists the accepted ones
+        pub fn accepted_ranges(&self, range: PieceRange) -> Vec<PieceRange> {
+            self.explore(range, |_, _| ())
+        }
+
+        pub fn count_accepted(&self, range: PieceRange) -> u64 {
+            self.accepted_ranges(range)
+                .iter()
+                .map(PieceRange::count)
+                .sum()
+        }
+
+        /// Rules which never apply, including the rules of the workflows never reached from `in`
+        pub fn unreachable_rules(&self) -> Vec<UnreachableRule> {
+            let mut reached = self
+                .nodes
+                .iter()
+                .map(|node| vec![false; node.rules.len() + 1])
+                .collect::<Vec<_>>();
+            self.explore(PieceRange::default(), |id, rule| reached[id][rule] = true);
+            reached
+                .iter()
+                .enumerate()
+                .flat_map(|(id, rules)| {
+                    rules
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, reached)| !**reached)
+                        .map(move |(rule, _)| UnreachableRule {
+                            workflow: self.names[id].clone(),
+                            rule,
+                        })
+                })
+                .collect()
+        }
+
+        /// Sends the range through the tree, calls `visit` with every rule applied to some pieces
+        fn explore(
+            &self,
+            range: PieceRange,
+            mut visit: impl FnMut(WorkflowId, usize),
+        ) -> Vec<PieceRange> {
+            let mut accepted = Vec::new();
+            let mut ranges = vec![(Target::Workflow(self.start), range)];
+            while let Some((target, range)) = ranges.pop() {
+                if range.count() == 0 {
+                    continue;
+                }
+                let id = match target {
+                    Target::Accept => {
+                        accepted.push(range);
+                        continue;
+                    }
+                    Target::Reject => continue,
+                    Target::Workflow(id) => id,
+                };
+                let node = &self.nodes[id];
+                let mut remaining = vec![range];
+                for (rule, (condition, next)) in node.rules.iter().enumerate() {
+                    let mut others = Vec::new();
+                    for range in remaining {
+                        let (matching, rest) = range.split_condition(condition);
+                        if !matching.is_empty() {
+                            visit(id, rule);
+                        }
+                        ranges.extend(matching.into_iter().map(|range| (*next, range)));
+                        others.extend(rest);
+                    }
+                    remaining = others;
+                }
+                if !remaining.is_empty() {
+                    visit(id, node.rules.len());
+                }
+                ranges.extend(remaining.into_iter().map(|range| (node.default, range)));
+            }
+            accepted
+        }
+    }
+}
+
+#[cfg(feature = "fuzzing")]
+pub fn fuzz_parsers(input: &str) {
+    let _ = parsers::parse(input);
+}
+
+pub use parsers::{parse, ParseError, ParseErrorKind};
+
+mod parsers {
+    use std::{
+        collections::HashMap,
+        fmt::{self, Display},
+    };
+
     use nom::{
+        branch::alt,
         bytes::complete::{tag, take},
-        character::complete::{alpha1, line_ending, multispace1, u16},
-        multi::{separated_list0, separated_list1},
+        character::complete::{alpha1, u16},
+        combinator::all_consuming,
+        multi::separated_list0,
         IResult, Parser,
     };
     use nom_supreme::ParserExt;
 
     use super::*;
 
+    #[derive(Debug, PartialEq, Eq)]
+    pub struct ParseError {
+        /// Starting at 1
+        pub line: usize,
+        pub kind: ParseErrorKind,
+    }
+
+    #[derive(Debug, PartialEq, Eq)]
+    pub enum ParseErrorKind {
+        /// Starting at 1
+        Syntax {
+            column: usize,
+        },
+        UndefinedTarget(String),
+        DuplicateWorkflow(String),
+    }
+
+    impl Display for ParseError {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "line {} : ", self.line)?;
+            match &self.kind {
+                ParseErrorKind::Syntax { column } => write!(f, "syntax error at column {column}"),
+                ParseErrorKind::UndefinedTarget(name) => write!(f, "undefined workflow {name}"),
+                ParseErrorKind::DuplicateWorkflow(name) => {
+                    write!(f, "workflow {name} is already defined")
+                }
+            }
+        }
+    }
+
     fn piece(input: &str) -> IResult<&str, Piece> {
         let (input, _) = tag("{x=")(input)?;
         let (input, x) = u16(input)?;
@@ -233,10 +615,14 @@ mod parsers {
     }
 
     fn condition(input: &str) -> IResult<&str, Condition> {
-        let (input, xmas) = xmas(input)?;
-        let (input, ord) = greater_or_lesser(input)?;
-        let (input, value) = u16(input)?;
-        let condition = Condition { xmas, ord, value };
+        let (input, field) = xmas(input)?;
+        let (input, comparison) = comparison(input)?;
+        let (input, operand) = alt((u16.map(Operand::Value), xmas.map(Operand::Field)))(input)?;
+        let condition = Condition {
+            xmas: field,
+            comparison,
+            operand,
+        };
         Ok((input, condition))
     }
 
@@ -252,8 +638,17 @@ mod parsers {
         take(1_usize).parse_from_str().parse(input)
     }
 
-    fn greater_or_lesser(input: &str) -> IResult<&str, GreaterOrLesser> {
-        take(1_usize).parse_from_str().parse(input)
+    fn comparison(input: &str) -> IResult<&str, Comparison> {
+        alt((
+            tag("<="),
+            tag(">="),
+            tag("=="),
+            tag("!="),
+            tag("<"),
+            tag(">"),
+        ))
+        .parse_from_str()
+        .parse(input)
     }
 
     fn workflow(input: &str) -> IResult<&str, WorkFlow> {
@@ -277,12 +672,76 @@ mod parsers {
         Ok((input, (name.to_string(), workflow)))
     }
 
-    pub fn part1(input: &str) -> IResult<&str, (HashMap<String, WorkFlow>, Vec<Piece>)> {
-        let (input, workflows) = separated_list1(line_ending, named_workflow)(input)?;
-        let (input, _) = multispace1(input)?;
-        let (input, pieces) = separated_list1(line_ending, piece)(input)?;
+    /// Parses a whole line, reports the column where the parser stopped
+    fn line<'a, O>(
+        parser: impl Parser<&'a str, O, nom::error::Error<&'a str>>,
+        line: &'a str,
+        number: usize,
+    ) -> Result<O, ParseError> {
+        all_consuming(parser)(line)
+            .map(|(_, output)| output)
+            .map_err(|e| {
+                let rest = match e {
+                    nom::Err::Error(e) | nom::Err::Failure(e) => e.input,
+                    nom::Err::Incomplete(_) => "",
+                };
+                ParseError {
+                    line: number,
+                    kind: ParseErrorKind::Syntax {
+                        column: line.len() - rest.len() + 1,
+                    },
+                }
+            })
+    }
 
-        Ok((input, (HashMap::from_iter(workflows.into_iter()), pieces)))
+    /// Workflows, then pieces after a blank line.
+    /// Targets are checked once all the workflows are known
+    pub fn parse(input: &str) -> Result<(HashMap<String, WorkFlow>, Vec<Piece>), ParseError> {
+        let mut lines = input.lines().enumerate().map(|(i, l)| (i + 1, l));
+        let mut workflows = HashMap::new();
+        let mut line_numbers = HashMap::new();
+        for (number, text) in lines.by_ref() {
+            if text.is_empty() {
+                break;
+            }
+            let (name, workflow) = line(named_workflow, text, number)?;
+            if workflows.contains_key(&name) {
+                return Err(ParseError {
+                    line: number,
+                    kind: ParseErrorKind::DuplicateWorkflow(name),
+                });
+            }
+            line_numbers.insert(name.clone(), number);
+            workflows.insert(name, workflow);
+        }
+        let pieces = lines
+            .filter(|(_, text)| !text.is_empty())
+            .map(|(number, text)| line(piece, text, number))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut undefined = workflows
+            .iter()
+            .flat_map(|(name, workflow)| {
+                let number = line_numbers[name];
+                workflow
+                    .conditions
+                    .iter()
+                    .map(|(_, target)| target)
+                    .chain([&workflow.default])
+                    .map(move |target| (number, target))
+            })
+            .filter(|(_, target)| {
+                !["A", "R"].contains(&target.as_str()) && !workflows.contains_key(*target)
+            })
+            .collect::<Vec<_>>();
+        undefined.sort();
+        if let Some((line, target)) = undefined.first() {
+            return Err(ParseError {
+                line: *line,
+                kind: ParseErrorKind::UndefinedTarget(target.to_string()),
+            });
+        }
+        Ok((workflows, pieces))
     }
 }
 
@@ -299,4 +758,136 @@ mod tests {
     fn test_part2() {
         Day::test_part2(167409079868000)
     }
+
+    fn compile(input: &str) -> Result<DecisionTree, CompileError> {
+        let (workflows, _) = parsers::parse(input).unwrap();
+        DecisionTree::compile(&workflows)
+    }
+
+    #[test]
+    fn test_accepted_ranges() {
+        let tree = compile(Day::SAMPLE_PART2).unwrap();
+        let ranges = tree.accepted_ranges(PieceRange::default());
+        // Disjoint hyper-rectangles : every accepted piece is in exactly one of them
+        let (_, pieces) = parsers::parse(Day::SAMPLE_PART1).unwrap();
+        for piece in &pieces {
+            let inside = ranges
+                .iter()
+                .filter(|r| {
+                    r.x.contains(&piece.x)
+                        && r.m.contains(&piece.m)
+                        && r.a.contains(&piece.a)
+                        && r.s.contains(&piece.s)
+                })
+                .count();
+            assert_eq!(inside, tree.accepts(piece) as usize);
+        }
+
+        let query = PieceRange {
+            x: 1..1001,
+            ..Default::default()
+        };
+        let expected = ranges
+            .iter()
+            .map(|r| {
+                let x = r.x.start.max(1)..r.x.end.min(1001);
+                PieceRange { x, ..r.clone() }.count()
+            })
+            .sum::<u64>();
+        assert_eq!(tree.count_accepted(query), expected);
+    }
+
+    #[test]
+    fn test_diagnostics() {
+        let tree =
+            compile("in{x>10:R,x>20:A,a}\na{m<5:A,R}\nb{s>0:A,A}\n\n{x=1,m=2,a=3,s=4}").unwrap();
+        assert_eq!(
+            tree.unreachable_rules(),
+            [
+                UnreachableRule {
+                    workflow: "b".into(),
+                    rule: 0
+                },
+                UnreachableRule {
+                    workflow: "b".into(),
+                    rule: 1
+                },
+                UnreachableRule {
+                    workflow: "in".into(),
+                    rule: 1
+                },
+            ]
+        );
+        assert_eq!(
+            compile("in{x>10:R,a}\na{m<5:in,b}\nb{s>0:A,A}\n\n{x=1,m=2,a=3,s=4}").unwrap_err(),
+            CompileError::Cycle(vec!["a".into(), "in".into()])
+        );
+        assert_eq!(
+            parse("in{x>10:R,a}\na{m<5:A,c}\n\n{x=1,m=2,a=3,s=4}").unwrap_err(),
+            ParseError {
+                line: 2,
+                kind: ParseErrorKind::UndefinedTarget("c".into())
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let error = |input| parse(input).unwrap_err().to_string();
+        assert_eq!(
+            error("in{x>10:R,A}\na{m=<5:A,R}"),
+            "line 2 : syntax error at column 3"
+        );
+        assert_eq!(
+            error("in{x>10:R,A}\nin{m<5:A,R}"),
+            "line 2 : workflow in is already defined"
+        );
+        assert_eq!(
+            error("in{x>10:R,A}\n\n{x=1,m=2,a=3,s=4}\n{x=1,m=2,s=4}"),
+            "line 4 : syntax error at column 9"
+        );
+    }
+
+    #[test]
+    fn test_extended_conditions() {
+        let input = "in{x<=m:a,s!=3:b,A}\na{x==m:R,a>=x:A,R}\nb{s>a:R,m>=2:A,R}";
+        let tree = compile(input).unwrap();
+        let range = PieceRange {
+            x: 1..7,
+            m: 1..6,
+            a: 2..8,
+            s: 1..5,
+        };
+        let ranges = tree.accepted_ranges(range.clone());
+        let mut count = 0;
+        for x in range.x.clone() {
+            for m in range.m.clone() {
+                for a in range.a.clone() {
+                    for s in range.s.clone() {
+                        let piece = Piece { x, m, a, s };
+                        let expected = if x <= m {
+                            x != m && a >= x
+                        } else if s != 3 {
+                            s <= a && m >= 2
+                        } else {
+                            true
+                        };
+                        assert_eq!(tree.accepts(&piece), expected, "{piece:?}");
+                        let inside = ranges
+                            .iter()
+                            .filter(|r| {
+                                r.x.contains(&x)
+                                    && r.m.contains(&m)
+                                    && r.a.contains(&a)
+                                    && r.s.contains(&s)
+                            })
+                            .count();
+                        assert_eq!(inside, expected as usize, "{piece:?}");
+                        count += expected as u64;
+                    }
+                }
+            }
+        }
+        assert_eq!(tree.count_accepted(range), count);
+    }
 }
@@ -1,4 +1,7 @@
-use std::collections::{HashMap, VecDeque};
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt::Write,
+};
 
 use aoc::Aoc;
 use day08::lcm;
@@ -13,224 +16,508 @@ impl Aoc for Day {
     const SAMPLE_PART2: &'static str = include_str!("../inputs/sample2.txt");
 
     fn part1(input: &str) -> Self::OUTPUT {
-        let mut nodes = parsers::part1(input);
+        let network = Network::new(input);
+        let mut simulator = Simulator::new(&network);
         let mut low_count = 0;
         let mut high_count = 0;
         for _ in 0..1000 {
-            let mut pulses = VecDeque::from([Button::press()]);
-            while let Some(pulse) = pulses.pop_front() {
-                match &pulse.pulse {
-                    PulseType::Low => low_count += 1,
-                    PulseType::High => high_count += 1,
-                }
-                let Some(node) = nodes.get_mut(&pulse.destination) else {
-                    continue;
-                };
-                for pulse in node.pulse(&pulse) {
-                    pulses.push_back(pulse);
-                }
-            }
+            let stats = simulator.press();
+            low_count += stats.low;
+            high_count += stats.high;
         }
         low_count * high_count
     }
 
     fn part2(input: &str) -> Self::OUTPUT {
-        let mut nodes = parsers::part1(input);
-        let last_to_rx = nodes
-            .iter()
-            .find(|(_, node)| node.destination.contains(&"rx".into()))
-            .unwrap();
-        let last_name = last_to_rx.0.clone();
-        let NodeType::Conjunction(inputs) = &last_to_rx.1.nodetype else {
-            panic!("Wong node type")
-        };
-        let mut number_presses =
-            HashMap::<String, Option<u64>>::from_iter(inputs.keys().map(|key| (key.clone(), None)));
-
-        for n in 1..=10000 {
-            let mut pulses = VecDeque::from([Button::press()]);
-            while let Some(pulse) = pulses.pop_front() {
-                let Some(node) = nodes.get_mut(&pulse.destination) else {
-                    continue;
-                };
-                for pulse in node.pulse(&pulse) {
-                    pulses.push_back(pulse);
-                }
-                if pulse.destination == last_name && pulse.pulse == PulseType::High {
-                    let number = number_presses.get_mut(&pulse.origin).unwrap();
-                    if number.is_none() {
-                        *number = Some(n)
-                    }
-                }
-            }
-            if number_presses.values().all(|n| n.is_some()) {
-                break;
-            }
-        }
-        let numbers = number_presses
-            .values()
-            .map(|n| n.unwrap())
-            .collect::<Vec<_>>();
-        numbers.into_iter().reduce(lcm).unwrap()
+        let network = Network::new(input);
+        analysis::analyse(&network)
+            .unwrap_or_else(|e| panic!("Input does not fit the counters pattern : {e}"))
+            .presses()
     }
 }
 
-struct Button {}
-impl Button {
-    fn press() -> Pulse {
-        Pulse {
-            origin: "button".into(),
-            pulse: PulseType::Low,
-            destination: "broadcaster".into(),
-        }
-    }
+/// Index of a module in the [`Network`]
+pub type ModuleId = usize;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ModuleKind {
+    Broadcaster,
+    FlipFlop,
+    Conjunction,
+    /// Modules which only receive pulses, like `rx`
+    Output,
 }
 
 #[derive(Debug)]
-struct Node {
-    nodetype: NodeType,
-    destination: Vec<String>,
+pub struct Module {
+    pub kind: ModuleKind,
+    pub inputs: Vec<ModuleId>,
+    pub outputs: Vec<ModuleId>,
 }
-impl Node {
-    fn pulse(&mut self, pulse: &Pulse) -> Vec<Pulse> {
-        match &mut self.nodetype {
-            NodeType::Broadcaster => self
-                .destination
-                .iter()
-                .cloned()
-                .map(|destination| Pulse {
-                    origin: pulse.destination.clone(),
-                    pulse: pulse.pulse.clone(),
-                    destination,
-                })
-                .collect(),
-            NodeType::FlipFlop(state) if pulse.pulse == PulseType::Low => {
-                state.swap();
-                self.destination
-                    .iter()
-                    .cloned()
-                    .map(|destination| Pulse {
-                        origin: pulse.destination.clone(),
-                        pulse: state.clone(),
-                        destination,
-                    })
-                    .collect()
+
+/// The modules and their wiring, names are only used when building the network
+#[derive(Debug)]
+pub struct Network {
+    names: Vec<String>,
+    ids: HashMap<String, ModuleId>,
+    modules: Vec<Module>,
+    /// For every output of every module : the destination, and the position of the wire in its inputs
+    wires: Vec<Vec<(ModuleId, usize)>>,
+    broadcaster: Option<ModuleId>,
+}
+impl Network {
+    pub fn new(input: &str) -> Self {
+        let mut network = Self {
+            names: Vec::new(),
+            ids: HashMap::new(),
+            modules: Vec::new(),
+            wires: Vec::new(),
+            broadcaster: None,
+        };
+        let lines = input
+            .lines()
+            .map(|line| {
+                parsers::module(line)
+                    .unwrap_or_else(|e| panic!("Parser failed {e:?}"))
+                    .1
+            })
+            .collect::<Vec<_>>();
+        for (name, kind, _) in &lines {
+            let id = network.intern(name);
+            network.modules[id].kind = *kind;
+        }
+        for (name, _, outputs) in &lines {
+            let id = network.ids[*name];
+            for output in outputs {
+                let destination = network.intern(output);
+                let position = network.modules[destination].inputs.len();
+                network.modules[destination].inputs.push(id);
+                network.modules[id].outputs.push(destination);
+                network.wires[id].push((destination, position));
             }
-            NodeType::FlipFlop(_) => Vec::new(),
-            NodeType::Conjunction(inputs) => {
-                let input = inputs.get_mut(&pulse.origin).unwrap();
-                *input = pulse.pulse.clone();
-                let out_pulse = match inputs.values().all(|p| *p == PulseType::High) {
-                    true => PulseType::Low,
-                    false => PulseType::High,
-                };
-                self.destination
-                    .iter()
-                    .cloned()
-                    .map(|destination| Pulse {
-                        origin: pulse.destination.clone(),
-                        pulse: out_pulse.clone(),
-                        destination,
-                    })
-                    .collect()
+        }
+        network.broadcaster = network.id("broadcaster");
+        network
+    }
+
+    fn intern(&mut self, name: &str) -> ModuleId {
+        if let Some(id) = self.ids.get(name) {
+            return *id;
+        }
+        let id = self.modules.len();
+        self.names.push(name.to_string());
+        self.ids.insert(name.to_string(), id);
+        self.modules.push(Module {
+            kind: ModuleKind::Output,
+            inputs: Vec::new(),
+            outputs: Vec::new(),
+        });
+        self.wires.push(Vec::new());
+        id
+    }
+
+    pub fn id(&self, name: &str) -> Option<ModuleId> {
+        self.ids.get(name).copied()
+    }
+
+    pub fn name(&self, id: ModuleId) -> &str {
+        &self.names[id]
+    }
+
+    pub fn module(&self, id: ModuleId) -> &Module {
+        &self.modules[id]
+    }
+
+    pub fn len(&self) -> usize {
+        self.modules.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.modules.is_empty()
+    }
+
+    /// Graphviz representation of the network, render with `dot -Tsvg`
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph network {\n");
+        for (id, module) in self.modules.iter().enumerate() {
+            let (label, shape) = match module.kind {
+                ModuleKind::Broadcaster => (self.names[id].clone(), "box"),
+                ModuleKind::FlipFlop => (format!("%{}", self.names[id]), "ellipse"),
+                ModuleKind::Conjunction => (format!("&{}", self.names[id]), "diamond"),
+                ModuleKind::Output => (self.names[id].clone(), "doublecircle"),
+            };
+            writeln!(
+                dot,
+                "    {} [label=\"{label}\", shape={shape}];",
+                self.names[id]
+            )
+            .unwrap();
+        }
+        for (id, module) in self.modules.iter().enumerate() {
+            for output in &module.outputs {
+                writeln!(dot, "    {} -> {};", self.names[id], self.names[*output]).unwrap();
             }
         }
+        dot.push_str("}\n");
+        dot
     }
 }
 
-#[derive(Debug)]
-enum NodeType {
-    Broadcaster,
-    FlipFlop(PulseType),
-    Conjunction(HashMap<String, PulseType>),
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum PulseType {
+    Low,
+    High,
 }
 
-#[derive(Debug)]
-struct Pulse {
-    origin: String,
-    pulse: PulseType,
-    destination: String,
+/// Pulses sent during a single button press
+#[derive(Debug, PartialEq, Eq, Default)]
+pub struct PressStats {
+    pub low: u64,
+    pub high: u64,
+    /// Index of the probes triggered during this press
+    pub triggered: Vec<usize>,
 }
 
+/// Watches the pulses emitted by a module
 #[derive(Debug, PartialEq, Eq, Clone)]
-enum PulseType {
-    Low,
-    High,
+pub struct Probe {
+    pub module: ModuleId,
+    pub pulse: PulseType,
+    /// Number of times the module emitted the pulse
+    pub count: u64,
+    pub first_press: Option<u64>,
+    pub last_press: Option<u64>,
+}
+
+/// State of every module, and the button
+pub struct Simulator<'a> {
+    network: &'a Network,
+    flip_flops: Vec<bool>,
+    /// Last pulse received by the conjunctions, on each input
+    memory: Vec<Vec<PulseType>>,
+    /// Number of inputs of the conjunctions which last received a High pulse
+    high_inputs: Vec<usize>,
+    /// (destination, input position, pulse)
+    queue: VecDeque<(ModuleId, usize, PulseType)>,
+    probes: Vec<Probe>,
+    presses: u64,
 }
-impl PulseType {
-    fn swap(&mut self) {
-        *self = match self {
-            PulseType::Low => PulseType::High,
-            PulseType::High => PulseType::Low,
+impl<'a> Simulator<'a> {
+    pub fn new(network: &'a Network) -> Self {
+        Self {
+            network,
+            flip_flops: vec![false; network.len()],
+            memory: network
+                .modules
+                .iter()
+                .map(|module| vec![PulseType::Low; module.inputs.len()])
+                .collect(),
+            high_inputs: vec![0; network.len()],
+            queue: VecDeque::new(),
+            probes: Vec::new(),
+            presses: 0,
+        }
+    }
+
+    /// Starts watching a module, returns the probe index
+    pub fn add_probe(&mut self, module: ModuleId, pulse: PulseType) -> usize {
+        self.probes.push(Probe {
+            module,
+            pulse,
+            count: 0,
+            first_press: None,
+            last_press: None,
+        });
+        self.probes.len() - 1
+    }
+
+    pub fn probe(&self, index: usize) -> &Probe {
+        &self.probes[index]
+    }
+
+    /// Number of times the button was pressed
+    pub fn presses(&self) -> u64 {
+        self.presses
+    }
+
+    pub fn press(&mut self) -> PressStats {
+        self.presses += 1;
+        let mut stats = PressStats {
+            low: 1,
+            ..Default::default()
+        };
+        if let Some(broadcaster) = self.network.broadcaster {
+            self.queue.push_back((broadcaster, 0, PulseType::Low));
+        }
+        while let Some((id, position, pulse)) = self.queue.pop_front() {
+            let output = match self.network.modules[id].kind {
+                ModuleKind::Broadcaster => pulse,
+                ModuleKind::FlipFlop if pulse == PulseType::Low => {
+                    self.flip_flops[id] = !self.flip_flops[id];
+                    match self.flip_flops[id] {
+                        true => PulseType::High,
+                        false => PulseType::Low,
+                    }
+                }
+                ModuleKind::FlipFlop | ModuleKind::Output => continue,
+                ModuleKind::Conjunction => {
+                    let memory = &mut self.memory[id][position];
+                    match (*memory, pulse) {
+                        (PulseType::Low, PulseType::High) => self.high_inputs[id] += 1,
+                        (PulseType::High, PulseType::Low) => self.high_inputs[id] -= 1,
+                        _ => (),
+                    }
+                    *memory = pulse;
+                    match self.high_inputs[id] == self.memory[id].len() {
+                        true => PulseType::Low,
+                        false => PulseType::High,
+                    }
+                }
+            };
+            self.emit(id, output, &mut stats);
+        }
+        stats
+    }
+
+    fn emit(&mut self, id: ModuleId, pulse: PulseType, stats: &mut PressStats) {
+        for (index, probe) in self.probes.iter_mut().enumerate() {
+            if probe.module == id && probe.pulse == pulse {
+                probe.count += 1;
+                probe.first_press.get_or_insert(self.presses);
+                probe.last_press = Some(self.presses);
+                if !stats.triggered.contains(&index) {
+                    stats.triggered.push(index);
+                }
+            }
+        }
+        let wires = &self.network.wires[id];
+        match pulse {
+            PulseType::Low => stats.low += wires.len() as u64,
+            PulseType::High => stats.high += wires.len() as u64,
+        }
+        for (destination, position) in wires {
+            self.queue.push_back((*destination, *position, pulse));
         }
     }
+
+    /// Keeps pressing the button until `module` emits `pulse`, at most `limit` more times.
+    /// Returns the number of presses since the start of the simulation
+    pub fn presses_until(&mut self, module: ModuleId, pulse: PulseType, limit: u64) -> Option<u64> {
+        let probe = self.add_probe(module, pulse);
+        let found = (0..limit).find_map(|_| {
+            self.press();
+            self.probes[probe].first_press
+        });
+        self.probes.pop();
+        found
+    }
 }
 
-mod parsers {
-    use nom::{
-        branch::alt, bytes::complete::tag, character::complete::alpha1, multi::separated_list1,
-        IResult, Parser,
-    };
+/// Recognises the circuit behind rx : binary counters made of flip-flop chains,
+/// each reset by a conjunction when it reaches its period.
+///
+/// Every counter only sends a High pulse towards rx on multiples of its period,
+/// rx receives a Low pulse when all the counters do so during the same press.
+pub mod analysis {
+    use std::fmt::{self, Display};
 
     use super::*;
 
-    pub fn part1(input: &str) -> HashMap<String, Node> {
-        let mut output = HashMap::new();
-        let mut input_nodes = HashMap::<String, Vec<String>>::new();
-        for line in input.lines() {
-            let (name, node) = node(line).unwrap().1;
-            for dest in &node.destination {
-                let inputs = input_nodes.entry(dest.clone()).or_insert(Vec::new());
-                inputs.push(name.clone());
-            }
-            output.insert(name, node);
+    #[derive(Debug, PartialEq, Eq)]
+    pub struct Counter {
+        /// Flip-flops, from the lowest bit
+        pub bits: Vec<ModuleId>,
+        /// Conjunction reading the bits set in the period
+        pub hub: ModuleId,
+        /// Conjunction inverting the hub output, feeding the module before rx
+        pub inverter: ModuleId,
+        pub period: u64,
+    }
+
+    #[derive(Debug, PartialEq, Eq)]
+    pub struct Analysis {
+        pub counters: Vec<Counter>,
+        /// The conjunction sending pulses to rx
+        pub last: ModuleId,
+    }
+    impl Analysis {
+        /// Number of presses before rx receives a Low pulse
+        pub fn presses(&self) -> u64 {
+            self.counters
+                .iter()
+                .map(|counter| counter.period)
+                .fold(1, lcm)
         }
-        for (name, node) in &mut output {
-            if let NodeType::Conjunction(inputs) = &mut node.nodetype {
-                for input in input_nodes.get(name).unwrap() {
-                    inputs.insert(input.clone(), PulseType::Low);
+    }
+
+    #[derive(Debug, PartialEq, Eq)]
+    pub enum AnalysisError {
+        MissingModule(&'static str),
+        UnexpectedWiring {
+            module: String,
+            expected: &'static str,
+        },
+    }
+    impl Display for AnalysisError {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            match self {
+                AnalysisError::MissingModule(name) => write!(f, "no module named {name}"),
+                AnalysisError::UnexpectedWiring { module, expected } => {
+                    write!(f, "module {module} should have {expected}")
                 }
             }
         }
-        output
     }
 
-    fn node(input: &str) -> IResult<&str, (String, Node)> {
-        alt((broadcaster, flip_flop, conjunction))(input)
+    pub fn analyse(network: &Network) -> Result<Analysis, AnalysisError> {
+        let wiring = |id: ModuleId, expected| AnalysisError::UnexpectedWiring {
+            module: network.name(id).to_string(),
+            expected,
+        };
+        let rx = network.id("rx").ok_or(AnalysisError::MissingModule("rx"))?;
+        let broadcaster = network
+            .id("broadcaster")
+            .ok_or(AnalysisError::MissingModule("broadcaster"))?;
+        let last = match network.module(rx).inputs[..] {
+            [last] if network.module(last).kind == ModuleKind::Conjunction => last,
+            _ => return Err(wiring(rx, "a single conjunction as input")),
+        };
+
+        let counters = network
+            .module(broadcaster)
+            .outputs
+            .iter()
+            .map(|head| counter(network, *head))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut inverters = counters
+            .iter()
+            .map(|counter| counter.inverter)
+            .collect::<Vec<_>>();
+        inverters.sort();
+        let mut inputs = network.module(last).inputs.clone();
+        inputs.sort();
+        if inverters != inputs {
+            return Err(wiring(last, "exactly one input from every counter"));
+        }
+        Ok(Analysis { counters, last })
     }
 
-    fn broadcaster(input: &str) -> IResult<&str, (String, Node)> {
-        let (input, _) = tag("broadcaster -> ")(input)?;
-        let (input, destination) = separated_list1(tag(", "), alpha1.map(String::from))(input)?;
-        let node = Node {
-            nodetype: NodeType::Broadcaster,
-            destination,
+    fn counter(network: &Network, head: ModuleId) -> Result<Counter, AnalysisError> {
+        let wiring = |id: ModuleId, expected| AnalysisError::UnexpectedWiring {
+            module: network.name(id).to_string(),
+            expected,
         };
-        Ok((input, ("broadcaster".into(), node)))
-    }
+        let kind = |id: ModuleId| network.module(id).kind;
 
-    fn flip_flop(input: &str) -> IResult<&str, (String, Node)> {
-        let (input, _) = tag("%")(input)?;
-        let (input, name) = alpha1(input)?;
-        let (input, _) = tag(" -> ")(input)?;
-        let (input, destination) = separated_list1(tag(", "), alpha1.map(String::from))(input)?;
-        let node = Node {
-            nodetype: NodeType::FlipFlop(PulseType::Low),
-            destination,
+        // Follow the chain of flip-flops, the bits of the period are the ones feeding the hub
+        let mut bits = Vec::new();
+        let mut hub = None;
+        let mut period = 0_u64;
+        let mut current = Some(head);
+        while let Some(id) = current {
+            if kind(id) != ModuleKind::FlipFlop {
+                return Err(wiring(id, "the flip-flop kind, as a counter bit"));
+            }
+            if bits.len() == 63 || bits.contains(&id) {
+                return Err(wiring(head, "a chain of less than 64 flip-flops"));
+            }
+            current = None;
+            for output in &network.module(id).outputs {
+                match kind(*output) {
+                    ModuleKind::FlipFlop if current.is_none() => current = Some(*output),
+                    ModuleKind::Conjunction if hub.is_none() || hub == Some(*output) => {
+                        hub = Some(*output);
+                        period |= 1 << bits.len();
+                    }
+                    _ => {
+                        return Err(wiring(
+                            id,
+                            "outputs to the next bit and the counter conjunction only",
+                        ))
+                    }
+                }
+            }
+            bits.push(id);
+        }
+        let hub = hub.ok_or_else(|| wiring(head, "a conjunction reading the counter"))?;
+
+        let hub_module = network.module(hub);
+        if hub_module.inputs.len() != period.count_ones() as usize {
+            return Err(wiring(hub, "inputs from its counter bits only"));
+        }
+        if period & 1 == 0 || period >> (bits.len() - 1) == 0 {
+            return Err(wiring(
+                hub,
+                "the lowest and highest bits of its counter as inputs",
+            ));
+        }
+        // Setting the bits at 0, and flipping the lowest bit, overflows the counter back to 0
+        let mut reset = hub_module
+            .outputs
+            .iter()
+            .copied()
+            .filter(|output| kind(*output) == ModuleKind::FlipFlop)
+            .collect::<Vec<_>>();
+        reset.sort();
+        let mut expected = (0..bits.len())
+            .filter(|bit| *bit == 0 || period & (1 << bit) == 0)
+            .map(|bit| bits[bit])
+            .collect::<Vec<_>>();
+        expected.sort();
+        if reset != expected {
+            return Err(wiring(hub, "outputs to the lowest bit and the unset bits"));
+        }
+
+        let inverter = match hub_module.outputs[..]
+            .iter()
+            .filter(|output| kind(**output) != ModuleKind::FlipFlop)
+            .collect::<Vec<_>>()[..]
+        {
+            [inverter]
+                if kind(*inverter) == ModuleKind::Conjunction
+                    && network.module(*inverter).inputs == [hub] =>
+            {
+                *inverter
+            }
+            _ => return Err(wiring(hub, "a single inverter conjunction as output")),
         };
-        Ok((input, (name.into(), node)))
+        Ok(Counter {
+            bits,
+            hub,
+            inverter,
+            period,
+        })
+    }
+}
+
+#[cfg(feature = "fuzzing")]
+pub fn fuzz_parsers(input: &str) {
+    for line in input.lines() {
+        let _ = parsers::module(line);
     }
+}
 
-    fn conjunction(input: &str) -> IResult<&str, (String, Node)> {
-        let (input, _) = tag("&")(input)?;
-        let (input, name) = alpha1(input)?;
+mod parsers {
+    use nom::{
+        branch::alt, bytes::complete::tag, character::complete::alpha1, multi::separated_list1,
+        IResult, Parser,
+    };
+
+    use super::*;
+
+    pub fn module(input: &str) -> IResult<&str, (&str, ModuleKind, Vec<&str>)> {
+        let (input, (name, kind)) = alt((
+            tag("broadcaster").map(|name| (name, ModuleKind::Broadcaster)),
+            tag("%")
+                .and(alpha1)
+                .map(|(_, name)| (name, ModuleKind::FlipFlop)),
+            tag("&")
+                .and(alpha1)
+                .map(|(_, name)| (name, ModuleKind::Conjunction)),
+        ))(input)?;
         let (input, _) = tag(" -> ")(input)?;
-        let (input, destination) = separated_list1(tag(", "), alpha1.map(String::from))(input)?;
-        let node = Node {
-            nodetype: NodeType::Conjunction(HashMap::new()),
-            destination,
-        };
-        Ok((input, (name.into(), node)))
+        let (input, destination) = separated_list1(tag(", "), alpha1)(input)?;
+        Ok((input, (name, kind, destination)))
     }
 }
 
@@ -245,6 +532,96 @@ mod tests {
 
     #[test]
     fn test_part2() {
-        Day::test_part2(0)
+        Day::test_part2(35)
+    }
+
+    #[test]
+    fn test_analysis() {
+        for input in [Day::SAMPLE_PART2, Day::INPUT] {
+            let network = Network::new(input);
+            let analysis = analysis::analyse(&network).unwrap();
+            let mut simulator = Simulator::new(&network);
+            let probes = analysis
+                .counters
+                .iter()
+                .map(|counter| simulator.add_probe(counter.inverter, PulseType::High))
+                .collect::<Vec<_>>();
+            // Each counter fires on its period, and nowhere before the next multiple
+            let longest = analysis.counters.iter().map(|c| c.period).max().unwrap();
+            for _ in 0..2 * longest + 1 {
+                simulator.press();
+            }
+            for (counter, probe) in analysis.counters.iter().zip(probes) {
+                let probe = simulator.probe(probe);
+                assert_eq!(probe.first_press, Some(counter.period));
+                assert_eq!(probe.count, (2 * longest + 1) / counter.period);
+            }
+        }
+
+        let network = Network::new(Day::SAMPLE_PART2);
+        let mut simulator = Simulator::new(&network);
+        let last = network.id("last").unwrap();
+        assert_eq!(simulator.presses_until(last, PulseType::Low, 100), Some(35));
+    }
+
+    #[test]
+    fn test_analysis_errors() {
+        let error = |input| analysis::analyse(&Network::new(input)).unwrap_err();
+        assert_eq!(
+            error(Day::SAMPLE_PART1),
+            analysis::AnalysisError::MissingModule("rx")
+        );
+        let rewired = Day::SAMPLE_PART2.replace("&ha -> c, a, ia", "&ha -> a, ia");
+        assert_eq!(
+            error(&rewired).to_string(),
+            "module ha should have outputs to the lowest bit and the unset bits"
+        );
+        let extra = Day::SAMPLE_PART2.replace("%f -> hb", "%f -> hb, last");
+        assert_eq!(
+            error(&extra).to_string(),
+            "module f should have outputs to the next bit and the counter conjunction only"
+        );
+    }
+
+    #[test]
+    fn test_interesting_sample() {
+        let input = "broadcaster -> a\n%a -> inv, con\n&inv -> b\n%b -> con\n&con -> output";
+        assert_eq!(Day::part1(input), 11687500);
+
+        let network = Network::new(input);
+        let mut simulator = Simulator::new(&network);
+        let stats = simulator.press();
+        assert_eq!((stats.low, stats.high), (4, 4));
+
+        // a turns on every odd press, while b is still on : the output receives a Low pulse
+        let con = network.id("con").unwrap();
+        assert_eq!(simulator.presses_until(con, PulseType::Low, 10), Some(3));
+        assert_eq!(simulator.presses_until(con, PulseType::Low, 10), Some(5));
+        assert_eq!(simulator.presses_until(con, PulseType::Low, 1), None);
+        assert_eq!(simulator.presses(), 6);
+    }
+
+    #[test]
+    fn test_probes() {
+        let network = Network::new(Day::SAMPLE_PART1);
+        let mut simulator = Simulator::new(&network);
+        let inv = simulator.add_probe(network.id("inv").unwrap(), PulseType::High);
+        let c = simulator.add_probe(network.id("c").unwrap(), PulseType::High);
+        let stats = simulator.press();
+        assert_eq!((stats.low, stats.high), (8, 4));
+        assert_eq!(stats.triggered, [c, inv]);
+        assert_eq!(simulator.probe(inv).count, 1);
+        assert_eq!(simulator.probe(inv).first_press, Some(1));
+    }
+
+    #[test]
+    fn test_dot() {
+        let network = Network::new(Day::SAMPLE_PART1);
+        let dot = network.to_dot();
+        assert!(dot.starts_with("digraph network {\n"));
+        assert!(dot.contains("    inv [label=\"&inv\", shape=diamond];\n"));
+        assert!(dot.contains("    broadcaster -> a;\n"));
+        assert!(dot.contains("    inv -> a;\n"));
+        assert_eq!(dot.matches(" -> ").count(), 7);
     }
 }
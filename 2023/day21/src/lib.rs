@@ -1,44 +1,379 @@
 use std::str::FromStr;
 
 use aoc::Aoc;
+pub use reachability::{Garden, ParityCounts, Walk};
 
 pub struct Day;
 
 impl Aoc for Day {
-    type OUTPUT = u32;
+    type OUTPUT = u64;
     const DAY_NUMBER: u8 = 21;
     const INPUT: &'static str = include_str!("../inputs/input.txt");
     const SAMPLE_PART1: &'static str = include_str!("../inputs/sample1.txt");
     const SAMPLE_PART2: &'static str = include_str!("../inputs/sample2.txt");
 
     fn part1(input: &str) -> Self::OUTPUT {
+        let garden = Garden::new(input);
+        let mut walk = Walk::new(&garden, &[garden.start()]);
+        walk.walk(if cfg!(test) { 6 } else { 64 });
+        walk.count_exactly() as u64
+    }
+
+    fn part2(input: &str) -> Self::OUTPUT {
         let (grid, start) = parsers::part1(input);
+        let steps = if cfg!(test) { 5000 } else { 26_501_365 };
+        infinite::reachable(&grid, start, steps)
+    }
+}
+
+/// Reachability on the bounded map, by frontier expansion.
+///
+/// Only the plots reached for the first time (the frontier) are expanded at each step :
+/// the other plots reachable in exactly `n` steps were already reachable in `n - 2` steps,
+/// since the elf can step back and forth.
+pub mod reachability {
+    use super::*;
+
+    pub struct Garden {
+        width: usize,
+        height: usize,
+        rocks: Vec<bool>,
+        start: (usize, usize),
+    }
+    impl Garden {
+        pub fn new(input: &str) -> Self {
+            let (grid, start) = parsers::part1(input);
+            let rocks = grid.iter().flatten().map(|t| *t == Tile::Rock).collect();
+            Self {
+                width: grid[0].len(),
+                height: grid.len(),
+                rocks,
+                start,
+            }
+        }
+        pub fn start(&self) -> (usize, usize) {
+            self.start
+        }
+        pub fn width(&self) -> usize {
+            self.width
+        }
+        pub fn height(&self) -> usize {
+            self.height
+        }
+        pub fn rock(&self, (x, y): (usize, usize)) -> bool {
+            self.rocks[y * self.width + x]
+        }
+    }
+
+    /// Amount of plots reachable with an even or odd number of steps
+    #[derive(Debug, PartialEq, Eq, Clone, Copy)]
+    pub struct ParityCounts {
+        pub even: usize,
+        pub odd: usize,
+    }
+
+    /// Every plot the elves can be on after some steps, from one or several start points.
+    pub struct Walk<'a> {
+        garden: &'a Garden,
+        /// Rocks, and tiles blocked during the walk
+        blocked: Vec<bool>,
+        /// Plots reachable in exactly `steps` steps (index `steps % 2`) and `steps - 1` steps
+        reachable: [Vec<bool>; 2],
+        reachable_list: [Vec<usize>; 2],
+        /// Plots first reached in exactly `steps` steps
+        frontier: Vec<usize>,
+        distances: Vec<Option<u32>>,
+        steps: u32,
+        /// Steps left during which blocked tiles may have left isolated plots behind
+        recheck: u8,
+    }
+    impl<'a> Walk<'a> {
+        pub fn new(garden: &'a Garden, starts: &[(usize, usize)]) -> Self {
+            let size = garden.width * garden.height;
+            let mut walk = Self {
+                garden,
+                blocked: garden.rocks.clone(),
+                reachable: [vec![false; size], vec![false; size]],
+                reachable_list: [Vec::new(), Vec::new()],
+                frontier: Vec::new(),
+                distances: vec![None; size],
+                steps: 0,
+                recheck: 0,
+            };
+            for (x, y) in starts {
+                let index = y * garden.width + x;
+                if !walk.blocked[index] && !walk.reachable[0][index] {
+                    walk.reach(index, 0);
+                }
+            }
+            // Walled in starts can't step back and forth either
+            walk.recheck = 2;
+            walk
+        }
+
+        pub fn steps(&self) -> u32 {
+            self.steps
+        }
+
+        pub fn walk(&mut self, steps: u32) {
+            for _ in 0..steps {
+                self.step();
+            }
+        }
+
+        pub fn step(&mut self) {
+            let next = (self.steps as usize + 1) % 2;
+            let distance = self.steps + 1;
+            if self.recheck != 0 {
+                self.recheck -= 1;
+                self.remove_isolated(next);
+            }
+            for index in std::mem::take(&mut self.frontier) {
+                for neighbour in self.neighbours(index) {
+                    if !self.blocked[neighbour] && !self.reachable[next][neighbour] {
+                        self.reach(neighbour, distance);
+                    }
+                }
+            }
+            self.steps += 1;
+        }
+
+        /// Turns a tile into a rock for the rest of the walk.
+        /// Elves already standing on it are removed from the walk.
+        pub fn block(&mut self, (x, y): (usize, usize)) {
+            let index = y * self.garden.width + x;
+            self.blocked[index] = true;
+            for parity in 0..2 {
+                if self.reachable[parity][index] {
+                    self.reachable[parity][index] = false;
+                    self.reachable_list[parity].retain(|i| *i != index);
+                }
+            }
+            self.frontier.retain(|i| *i != index);
+            self.distances[index] = None;
+            // Plots surrounded by blocked tiles can't step back and forth anymore
+            self.recheck = 2;
+        }
+
+        /// Plots reachable in exactly `steps` steps
+        pub fn reachable_exactly(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+            let parity = self.steps as usize % 2;
+            self.reachable_list[parity]
+                .iter()
+                .map(|index| self.coords(*index))
+        }
+
+        pub fn count_exactly(&self) -> usize {
+            self.reachable_list[self.steps as usize % 2].len()
+        }
+
+        /// Plots reached at some point, in at most `steps` steps
+        pub fn reachable_at_most(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+            (0..self.distances.len())
+                .filter(|index| self.distances[*index].is_some())
+                .map(|index| self.coords(index))
+        }
+
+        pub fn count_at_most(&self) -> usize {
+            self.distances.iter().flatten().count()
+        }
+
+        /// Plots reachable in exactly the last even and odd number of steps
+        pub fn parity_counts(&self) -> ParityCounts {
+            let current = self.reachable_list[self.steps as usize % 2].len();
+            let previous = self.reachable_list[(self.steps as usize + 1) % 2].len();
+            match self.steps % 2 {
+                0 => ParityCounts {
+                    even: current,
+                    odd: previous,
+                },
+                _ => ParityCounts {
+                    even: previous,
+                    odd: current,
+                },
+            }
+        }
+
+        /// Number of steps needed to reach a plot for the first time
+        pub fn distance(&self, (x, y): (usize, usize)) -> Option<u32> {
+            self.distances[y * self.garden.width + x]
+        }
+
+        /// First reach distance of every tile, `None` for the tiles not reached yet
+        pub fn distance_map(&self) -> Vec<Vec<Option<u32>>> {
+            self.distances
+                .chunks(self.garden.width)
+                .map(|line| line.to_vec())
+                .collect()
+        }
+
+        fn reach(&mut self, index: usize, steps: u32) {
+            let parity = steps as usize % 2;
+            self.reachable[parity][index] = true;
+            self.reachable_list[parity].push(index);
+            self.frontier.push(index);
+            self.distances[index].get_or_insert(steps);
+        }
+
+        fn remove_isolated(&mut self, parity: usize) {
+            let mut list = std::mem::take(&mut self.reachable_list[parity]);
+            list.retain(|index| {
+                let free = self.neighbours(*index).any(|n| !self.blocked[n]);
+                if !free {
+                    self.reachable[parity][*index] = false;
+                }
+                free
+            });
+            self.reachable_list[parity] = list;
+        }
+
+        fn coords(&self, index: usize) -> (usize, usize) {
+            (index % self.garden.width, index / self.garden.width)
+        }
+
+        fn neighbours(&self, index: usize) -> impl Iterator<Item = usize> {
+            let (x, y) = self.coords(index);
+            let size = (self.garden.width, self.garden.height);
+            let width = self.garden.width;
+            Directions::All
+                .into_iter()
+                .filter_map(move |direction| direction.movement((x, y), size))
+                .map(move |(x, y)| y * width + x)
+        }
+    }
+}
+
+/// Reachability on the map repeated infinitely in every direction.
+///
+/// A plot is reachable in exactly `n` steps when its distance from the start is at most `n`,
+/// with the same parity as `n` (the elf can always step back and forth).
+/// Far from the start, the map repeats itself : the number of reachable plots becomes
+/// a quadratic function of the number of map periods walked, which can be extrapolated.
+mod infinite {
+    use std::collections::VecDeque;
+
+    use super::*;
+
+    /// Number of plots reachable in exactly `steps` steps, for any amount of steps
+    pub fn reachable(grid: &[Vec<Tile>], start: (usize, usize), steps: u64) -> u64 {
         let height = grid.len();
         let width = grid[0].len();
-        let mut previous = Vec::new();
-        let mut current = vec![start];
-
-        let range = if cfg!(test) { 6 } else { 64 };
-        for step in 1..=range {
-            previous = std::mem::take(&mut current);
-            for (x, y) in &previous {
-                let directions = Directions::All
-                    .into_iter()
-                    .filter_map(|direction| direction.movement((*x, *y), (width, height)));
-                for (x, y) in directions {
-                    if grid[y][x] != Tile::Rock {
-                        current.push((x, y));
+        // Going through one more map flips the parity of odd-sized maps : use 2 maps as a period
+        let period = 2 * lcm(width, height) as u64;
+        let remainder = steps % period;
+        let target = steps / period;
+
+        let mut samples = 5;
+        loop {
+            let max_distance = remainder + (samples - 1) * period;
+            let counts = ReachCounts::new(grid, start, max_distance);
+            if steps <= max_distance {
+                return counts.exactly(steps);
+            }
+            let values = (0..samples)
+                .map(|k| counts.exactly(remainder + k * period))
+                .collect::<Vec<_>>();
+            if let Some(value) = extrapolate(&values, target) {
+                return value;
+            }
+            samples *= 2;
+            assert!(
+                samples <= 64,
+                "Reachable plots do not grow quadratically with the map repetitions"
+            );
+        }
+    }
+
+    /// Extrapolates `values[k]` to any `k`, once the second differences have become constant.
+    /// The last 3 second differences must be equal.
+    fn extrapolate(values: &[u64], k: u64) -> Option<u64> {
+        let values = values.iter().map(|v| *v as i128).collect::<Vec<_>>();
+        let first = values.windows(2).map(|w| w[1] - w[0]).collect::<Vec<_>>();
+        let second = first.windows(2).map(|w| w[1] - w[0]).collect::<Vec<_>>();
+        let constant = *second.last()?;
+        if second.len() < 3 || second[second.len() - 3..].iter().any(|d| *d != constant) {
+            return None;
+        }
+
+        let last = values.len() - 1;
+        let n = k as i128 - last as i128;
+        let value = values[last] + n * first[last - 1] + n * (n + 1) / 2 * constant;
+        Some(value as u64)
+    }
+
+    /// Number of plots first reached at each distance from the start
+    pub struct ReachCounts(Vec<u64>);
+    impl ReachCounts {
+        /// Breadth first search on enough copies of the map to reach `max_distance`
+        pub fn new(grid: &[Vec<Tile>], start: (usize, usize), max_distance: u64) -> Self {
+            let height = grid.len();
+            let width = grid[0].len();
+            let tiles = max_distance as usize / width.min(height) + 1;
+            let total_width = (2 * tiles + 1) * width;
+            let total_height = (2 * tiles + 1) * height;
+
+            let mut distances = vec![u32::MAX; total_width * total_height];
+            let mut counts = vec![0; max_distance as usize + 1];
+            let start = (start.0 + tiles * width, start.1 + tiles * height);
+            distances[start.1 * total_width + start.0] = 0;
+            let mut queue = VecDeque::from([start]);
+            while let Some((x, y)) = queue.pop_front() {
+                let distance = distances[y * total_width + x];
+                counts[distance as usize] += 1;
+                if distance as u64 == max_distance {
+                    continue;
+                }
+                let neighbours = Directions::All.into_iter().filter_map(|direction| {
+                    direction.movement((x, y), (total_width, total_height))
+                });
+                for (x, y) in neighbours {
+                    let index = y * total_width + x;
+                    if grid[y % height][x % width] != Tile::Rock && distances[index] == u32::MAX {
+                        distances[index] = distance + 1;
+                        queue.push_back((x, y));
                     }
                 }
             }
-            current.sort();
-            current.dedup();
+            Self(counts)
+        }
+
+        /// Plots reachable in exactly `steps` steps
+        pub fn exactly(&self, steps: u64) -> u64 {
+            self.0
+                .iter()
+                .take(steps as usize + 1)
+                .skip(steps as usize % 2)
+                .step_by(2)
+                .sum()
         }
-        current.len() as u32
     }
 
-    fn part2(_input: &str) -> Self::OUTPUT {
-        todo!()
+    /// Step by step simulation, only usable for small amounts of steps
+    #[cfg(test)]
+    pub fn brute_force(grid: &[Vec<Tile>], start: (usize, usize), steps: u64) -> u64 {
+        use std::collections::HashSet;
+
+        let height = grid.len() as i64;
+        let width = grid[0].len() as i64;
+        let mut current = HashSet::from([(start.0 as i64, start.1 as i64)]);
+        for _ in 0..steps {
+            current = current
+                .iter()
+                .flat_map(|(x, y)| [(x + 1, *y), (x - 1, *y), (*x, y + 1), (*x, y - 1)])
+                .filter(|(x, y)| {
+                    grid[y.rem_euclid(height) as usize][x.rem_euclid(width) as usize] != Tile::Rock
+                })
+                .collect();
+        }
+        current.len() as u64
+    }
+
+    fn lcm(a: usize, b: usize) -> usize {
+        let (mut x, mut y) = (a, b);
+        while y != 0 {
+            (x, y) = (y, x % y);
+        }
+        a / x * b
     }
 }
 
@@ -126,6 +461,8 @@ mod parsers {
 
 #[cfg(test)]
 mod tests {
+    use std::collections::BTreeSet;
+
     use super::*;
 
     #[test]
@@ -135,6 +472,135 @@ mod tests {
 
     #[test]
     fn test_part2() {
-        Day::test_part2(0)
+        Day::test_part2(16733044)
+    }
+
+    #[test]
+    fn test_infinite() {
+        let (grid, start) = parsers::part1(Day::SAMPLE_PART2);
+        for (steps, expected) in [
+            (6, 16),
+            (10, 50),
+            (50, 1594),
+            (100, 6536),
+            (500, 167004),
+            (1000, 668697),
+        ] {
+            assert_eq!(
+                expected,
+                infinite::reachable(&grid, start, steps),
+                "{steps} steps"
+            );
+        }
+        for steps in (0..=150).step_by(5) {
+            assert_eq!(
+                infinite::brute_force(&grid, start, steps),
+                infinite::reachable(&grid, start, steps),
+                "{steps} steps"
+            );
+        }
+    }
+
+    /// Positions after each step, kept as a set and stepped from every position
+    fn naive_walk(
+        garden: &Garden,
+        starts: &[(usize, usize)],
+        blocks: &[(u32, (usize, usize))],
+        steps: u32,
+    ) -> Vec<(usize, usize)> {
+        let mut blocked = (0..garden.height())
+            .map(|y| {
+                (0..garden.width())
+                    .map(|x| garden.rock((x, y)))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        let size = (garden.width(), garden.height());
+        let mut current = starts.to_vec();
+        for step in 0..=steps {
+            for (_, (x, y)) in blocks.iter().filter(|(s, _)| *s == step) {
+                blocked[*y][*x] = true;
+            }
+            current.retain(|(x, y)| !blocked[*y][*x]);
+            if step == steps {
+                break;
+            }
+            current = current
+                .iter()
+                .flat_map(|pos| Directions::All.map(|dir| dir.movement(*pos, size)))
+                .flatten()
+                .filter(|(x, y)| !blocked[*y][*x])
+                .collect::<BTreeSet<_>>()
+                .into_iter()
+                .collect();
+        }
+        current.sort();
+        current.dedup();
+        current
+    }
+
+    #[test]
+    fn test_walk() {
+        let garden = Garden::new(Day::SAMPLE_PART1);
+        let starts = [garden.start(), (0, 0), (10, 10)];
+        let blocks = [
+            (3, (5, 4)),
+            (3, (4, 5)),
+            (4, (6, 5)),
+            (7, (5, 6)),
+            (7, (1, 2)),
+        ];
+        let mut walk = Walk::new(&garden, &starts);
+        for step in 0..=20 {
+            for (_, pos) in blocks.iter().filter(|(s, _)| *s == step) {
+                walk.block(*pos);
+            }
+            let mut exactly = walk.reachable_exactly().collect::<Vec<_>>();
+            exactly.sort();
+            assert_eq!(
+                naive_walk(&garden, &starts, &blocks, step),
+                exactly,
+                "{step} steps"
+            );
+            assert_eq!(exactly.len(), walk.count_exactly());
+            walk.step();
+        }
+    }
+
+    #[test]
+    fn test_walled_in_start() {
+        let garden = Garden::new(".#.\n#S#\n.#.");
+        let mut walk = Walk::new(&garden, &[garden.start(), (0, 0)]);
+        for step in 0..=4 {
+            let mut exactly = walk.reachable_exactly().collect::<Vec<_>>();
+            exactly.sort();
+            assert_eq!(
+                naive_walk(&garden, &[garden.start(), (0, 0)], &[], step),
+                exactly,
+                "{step} steps"
+            );
+            walk.step();
+        }
+    }
+
+    #[test]
+    fn test_distances() {
+        let garden = Garden::new(Day::SAMPLE_PART1);
+        let mut walk = Walk::new(&garden, &[garden.start()]);
+        walk.walk(6);
+        assert_eq!(walk.distance(garden.start()), Some(0));
+        assert_eq!(walk.distance((0, 0)), None);
+        let at_most = walk.reachable_at_most().collect::<Vec<_>>();
+        assert_eq!(at_most.len(), walk.count_at_most());
+        assert!(at_most.iter().all(|pos| walk.distance(*pos).unwrap() <= 6));
+        let map = walk.distance_map();
+        for (x, y) in walk.reachable_exactly() {
+            assert_eq!(map[y][x].unwrap() % 2, 0);
+        }
+        assert_eq!(walk.parity_counts().even, 16);
+        assert_eq!(
+            walk.parity_counts().even + walk.parity_counts().odd,
+            walk.count_at_most()
+        );
     }
 }
@@ -0,0 +1,10 @@
+#![no_main]
+
+use aoc_fuzz::{Mutation, Puzzle};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|case: (Puzzle, Vec<Mutation>)| {
+    let (puzzle, mutations) = case;
+    let input = aoc_fuzz::mutate(puzzle.valid_input(), &mutations);
+    puzzle.fuzz_parsers(&input);
+});
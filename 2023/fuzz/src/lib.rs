@@ -0,0 +1,272 @@
+//! Grammar-aware fuzzing : instead of feeding random bytes to the parsers,
+//! start from a valid puzzle input and apply token-level mutations to it.
+//! This reaches much deeper into the parsers than raw bytes, which fail on the first character.
+//!
+//! Raw targets (one per day) : `cargo fuzz run day05`
+//! Grammar-aware target (all days) : `cargo fuzz run mutated`
+//!
+//! Every fuzzed day exposes `fuzz_parsers`, behind its `fuzzing` feature.
+//! It runs the nom parsers of that day on the input : they may fail, but must never panic.
+
+use aoc::Aoc;
+use arbitrary::Arbitrary;
+
+#[derive(Debug, Arbitrary)]
+pub enum Input {
+    Sample1,
+    Sample2,
+    Real,
+}
+impl Input {
+    fn select<D: Aoc>(&self) -> &'static str {
+        match self {
+            Input::Sample1 => D::SAMPLE_PART1,
+            Input::Sample2 => D::SAMPLE_PART2,
+            Input::Real => D::INPUT,
+        }
+    }
+}
+
+macro_rules! puzzles {
+    ($($variant:ident => $day:ident),* $(,)?) => {
+        /// A day with nom parsers, and which of its valid inputs to start from
+        #[derive(Debug, Arbitrary)]
+        pub enum Puzzle {
+            $($variant(Input),)*
+        }
+        impl Puzzle {
+            pub fn valid_input(&self) -> &'static str {
+                match self {
+                    $(Puzzle::$variant(input) => input.select::<$day::Day>(),)*
+                }
+            }
+            pub fn fuzz_parsers(&self, input: &str) {
+                match self {
+                    $(Puzzle::$variant(_) => $day::fuzz_parsers(input),)*
+                }
+            }
+        }
+    };
+}
+
+puzzles! {
+    Day02 => day02,
+    Day04 => day04,
+    Day05 => day05,
+    Day06 => day06,
+    Day07 => day07,
+    Day08 => day08,
+    Day09 => day09,
+    Day12 => day12,
+    Day13 => day13,
+    Day14 => day14,
+    Day15 => day15,
+    Day16 => day16,
+    Day18 => day18,
+    Day19 => day19,
+    Day20 => day20,
+}
+
+/// Indexes are taken modulo the number of tokens or lines, so that every mutation applies
+#[derive(Debug, Arbitrary)]
+pub enum Mutation {
+    DeleteToken(u16),
+    DuplicateToken(u16),
+    SwapTokens(u16, u16),
+    ReplaceNumber(u16, Number),
+    InsertSymbol(u16, Symbol),
+    DeleteLine(u16),
+    DuplicateLine(u16),
+    SwapLines(u16, u16),
+    Truncate(u16),
+    /// Converts every line ending to CRLF
+    CrLf,
+}
+
+/// Edge case values for the integer parsers
+#[derive(Debug, Arbitrary)]
+pub enum Number {
+    Zero,
+    U8Max,
+    U16Max,
+    U32Max,
+    U64Max,
+    /// Does not fit in a u64
+    Overflow,
+    LeadingZeros,
+    Negative(i64),
+    Any(u64),
+}
+impl Number {
+    fn to_token(&self) -> String {
+        match self {
+            Number::Zero => "0".into(),
+            Number::U8Max => u8::MAX.to_string(),
+            Number::U16Max => u16::MAX.to_string(),
+            Number::U32Max => u32::MAX.to_string(),
+            Number::U64Max => u64::MAX.to_string(),
+            Number::Overflow => format!("{}0", u64::MAX),
+            Number::LeadingZeros => "0000000000000000000001".into(),
+            Number::Negative(n) => format!("-{}", n.unsigned_abs().max(1)),
+            Number::Any(n) => n.to_string(),
+        }
+    }
+}
+
+/// Symbols used by the puzzle grammars
+#[derive(Debug, Arbitrary)]
+pub enum Symbol {
+    Space,
+    Newline,
+    CrLf,
+    Comma,
+    Colon,
+    Semicolon,
+    Equals,
+    Minus,
+    Hash,
+    Dot,
+    Question,
+    Pipe,
+    Percent,
+    Ampersand,
+    LessThan,
+    GreaterThan,
+    OpenParen,
+    CloseParen,
+    OpenBrace,
+    CloseBrace,
+}
+impl Symbol {
+    fn to_token(&self) -> &'static str {
+        match self {
+            Symbol::Space => " ",
+            Symbol::Newline => "\n",
+            Symbol::CrLf => "\r\n",
+            Symbol::Comma => ",",
+            Symbol::Colon => ":",
+            Symbol::Semicolon => ";",
+            Symbol::Equals => "=",
+            Symbol::Minus => "-",
+            Symbol::Hash => "#",
+            Symbol::Dot => ".",
+            Symbol::Question => "?",
+            Symbol::Pipe => "|",
+            Symbol::Percent => "%",
+            Symbol::Ampersand => "&",
+            Symbol::LessThan => "<",
+            Symbol::GreaterThan => ">",
+            Symbol::OpenParen => "(",
+            Symbol::CloseParen => ")",
+            Symbol::OpenBrace => "{",
+            Symbol::CloseBrace => "}",
+        }
+    }
+}
+
+pub fn mutate(input: &str, mutations: &[Mutation]) -> String {
+    let mut tokens = tokenize(input);
+    for mutation in mutations {
+        mutation.apply(&mut tokens);
+    }
+    tokens.concat()
+}
+
+impl Mutation {
+    fn apply(&self, tokens: &mut Vec<String>) {
+        if tokens.is_empty() {
+            return;
+        }
+        let token = |index: &u16| *index as usize % tokens.len();
+        match self {
+            Mutation::DeleteToken(i) => {
+                tokens.remove(token(i));
+            }
+            Mutation::DuplicateToken(i) => {
+                let i = token(i);
+                tokens.insert(i, tokens[i].clone());
+            }
+            Mutation::SwapTokens(a, b) => {
+                let (a, b) = (token(a), token(b));
+                tokens.swap(a, b);
+            }
+            Mutation::ReplaceNumber(i, number) => {
+                let start = token(i);
+                let found = (start..tokens.len())
+                    .chain(0..start)
+                    .find(|i| tokens[*i].bytes().all(|b| b.is_ascii_digit()));
+                if let Some(i) = found {
+                    tokens[i] = number.to_token();
+                }
+            }
+            Mutation::InsertSymbol(i, symbol) => {
+                let i = token(i);
+                tokens.insert(i, symbol.to_token().into());
+            }
+            Mutation::Truncate(i) => {
+                let i = token(i);
+                tokens.truncate(i);
+            }
+            Mutation::DeleteLine(_) | Mutation::DuplicateLine(_) | Mutation::SwapLines(_, _) => {
+                let text = tokens.concat();
+                let mut lines = text.split_inclusive('\n').collect::<Vec<_>>();
+                let line = |index: &u16| *index as usize % lines.len();
+                match self {
+                    Mutation::DeleteLine(i) => {
+                        lines.remove(line(i));
+                    }
+                    Mutation::DuplicateLine(i) => {
+                        let i = line(i);
+                        lines.insert(i, lines[i]);
+                    }
+                    Mutation::SwapLines(a, b) => {
+                        let (a, b) = (line(a), line(b));
+                        lines.swap(a, b);
+                    }
+                    _ => unreachable!(),
+                }
+                *tokens = tokenize(&lines.concat());
+            }
+            Mutation::CrLf => {
+                let text = tokens.concat().replace("\r\n", "\n").replace('\n', "\r\n");
+                *tokens = tokenize(&text);
+            }
+        }
+    }
+}
+
+/// Splits the input into runs of digits, runs of letters, line endings, and single other characters
+fn tokenize(input: &str) -> Vec<String> {
+    #[derive(PartialEq)]
+    enum Class {
+        Digit,
+        Letter,
+        Other,
+    }
+    let class = |c: char| {
+        if c.is_ascii_digit() {
+            Class::Digit
+        } else if c.is_ascii_alphabetic() {
+            Class::Letter
+        } else {
+            Class::Other
+        }
+    };
+
+    let mut tokens = Vec::new();
+    let mut rest = input;
+    while let Some(first) = rest.chars().next() {
+        let len = if rest.starts_with("\r\n") {
+            2
+        } else if class(first) == Class::Other {
+            first.len_utf8()
+        } else {
+            rest.find(|c| class(c) != class(first))
+                .unwrap_or(rest.len())
+        };
+        let (token, remain) = rest.split_at(len);
+        tokens.push(token.to_string());
+        rest = remain;
+    }
+    tokens
+}
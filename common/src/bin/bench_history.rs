@@ -0,0 +1,318 @@
+//! Keeps track of the divan benchmark results across commits.
+//!
+//! Run from a workspace directory (e.g. `2023/`), results are stored in `bench_history.tsv` :
+//! - `cargo run --release --manifest-path ../common/Cargo.toml -- run [cargo bench args]`
+//!   runs the benches and records the medians for the current commit
+//! - `cargo run --release --manifest-path ../common/Cargo.toml -- report [base] [head] [--threshold 5]`
+//!   compares two recorded commits (by default the last two), exits with an error on regressions
+
+use std::{
+    collections::BTreeMap,
+    io::{BufRead, BufReader},
+    process::{Command, Stdio},
+    time::Duration,
+};
+
+const HISTORY_FILE: &str = "bench_history.tsv";
+const DEFAULT_THRESHOLD: f64 = 5.0;
+
+/// (day, part, variant)
+type Key = (String, String, String);
+
+#[derive(Debug, PartialEq)]
+struct Record {
+    commit: String,
+    key: Key,
+    median: Duration,
+}
+
+fn main() {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    match args.first().map(String::as_str) {
+        Some("run") => run(&args[1..]),
+        Some("report") => {
+            let (commits, threshold) = report_args(&args[1..]);
+            if !report(&commits, threshold) {
+                std::process::exit(1);
+            }
+        }
+        _ => {
+            eprintln!("Usage : bench_history run [cargo bench args]");
+            eprintln!("        bench_history report [base] [head] [--threshold <percent>]");
+            std::process::exit(2);
+        }
+    }
+}
+
+fn run(cargo_args: &[String]) {
+    let commit = current_commit();
+    println!("Running benchmarks for commit {commit}");
+
+    let mut child = Command::new("cargo")
+        .arg("bench")
+        .args(cargo_args)
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("Failed to run cargo bench");
+    let stdout = child.stdout.take().expect("Stdout is piped");
+
+    let mut parser = DivanParser::default();
+    let mut records = Vec::new();
+    for line in BufReader::new(stdout).lines() {
+        let line = line.expect("Benchmark output should be valid UTF-8");
+        println!("{line}");
+        if let Some((key, median)) = parser.parse_line(&line) {
+            records.push(Record {
+                commit: commit.clone(),
+                key,
+                median,
+            });
+        }
+    }
+    let status = child.wait().expect("cargo bench was not running");
+    if !status.success() {
+        eprintln!("cargo bench failed, results are not recorded");
+        std::process::exit(1);
+    }
+
+    // A new run on the same commit replaces the previous results
+    let mut history = read_history();
+    history.retain(|record| record.commit != commit);
+    history.extend(records);
+    write_history(&history);
+    println!("Results recorded in {HISTORY_FILE}");
+}
+
+fn report_args(args: &[String]) -> (Vec<String>, f64) {
+    let mut commits = Vec::new();
+    let mut threshold = DEFAULT_THRESHOLD;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg == "--threshold" {
+            threshold = args
+                .next()
+                .and_then(|t| t.parse().ok())
+                .expect("--threshold expects a percentage");
+        } else {
+            commits.push(arg.clone());
+        }
+    }
+    (commits, threshold)
+}
+
+/// Prints the comparison table, returns false if there is any regression
+fn report(commits: &[String], threshold: f64) -> bool {
+    let history = read_history();
+    let mut recorded = Vec::new();
+    for record in &history {
+        if !recorded.contains(&record.commit) {
+            recorded.push(record.commit.clone());
+        }
+    }
+    let find = |prefix: &String| {
+        recorded
+            .iter()
+            .rfind(|commit| commit.starts_with(prefix.as_str()))
+            .unwrap_or_else(|| panic!("No results recorded for commit {prefix}"))
+            .clone()
+    };
+    let (base, head) = match commits {
+        [] if recorded.len() >= 2 => (
+            recorded[recorded.len() - 2].clone(),
+            recorded[recorded.len() - 1].clone(),
+        ),
+        [] => panic!("At least two commits must be recorded to compare them"),
+        [base] => (find(base), recorded.last().unwrap().clone()),
+        [base, head, ..] => (find(base), find(head)),
+    };
+
+    let medians = |commit: &String| {
+        history
+            .iter()
+            .filter(|record| &record.commit == commit)
+            .map(|record| (record.key.clone(), record.median))
+            .collect::<BTreeMap<_, _>>()
+    };
+    let before = medians(&base);
+    let after = medians(&head);
+
+    println!("Comparing {base} -> {head} (threshold {threshold}%)");
+    let mut regressions = 0;
+    for ((day, part, variant), new) in &after {
+        let name = format!("{day} {part} {variant}");
+        let Some(old) = before.get(&(day.clone(), part.clone(), variant.clone())) else {
+            println!("{name:<60} {:>12} {:>12}   new", "", format_duration(*new));
+            continue;
+        };
+        let change = 100.0 * (new.as_secs_f64() / old.as_secs_f64() - 1.0);
+        let verdict = if change > threshold {
+            regressions += 1;
+            "REGRESSION"
+        } else if change < -threshold {
+            "improved"
+        } else {
+            ""
+        };
+        println!(
+            "{name:<60} {:>12} {:>12} {change:>+8.1}% {verdict}",
+            format_duration(*old),
+            format_duration(*new),
+        );
+    }
+    println!("{regressions} regression(s) above {threshold}%");
+    regressions == 0
+}
+
+fn current_commit() -> String {
+    let git = |args: &[&str]| {
+        let output = Command::new("git")
+            .args(args)
+            .output()
+            .expect("Failed to run git");
+        assert!(
+            output.status.success(),
+            "Benchmarks must run in a git repository"
+        );
+        String::from_utf8(output.stdout).expect("git output should be valid UTF-8")
+    };
+    let commit = git(&["rev-parse", "--short", "HEAD"]).trim().to_string();
+    if git(&["status", "--porcelain"]).trim().is_empty() {
+        commit
+    } else {
+        format!("{commit}-dirty")
+    }
+}
+
+fn read_history() -> Vec<Record> {
+    let Ok(content) = std::fs::read_to_string(HISTORY_FILE) else {
+        return Vec::new();
+    };
+    content
+        .lines()
+        .filter_map(|line| {
+            let [commit, day, part, variant, nanos] =
+                line.split('\t').collect::<Vec<_>>().try_into().ok()?;
+            Some(Record {
+                commit: commit.into(),
+                key: (day.into(), part.into(), variant.into()),
+                median: Duration::from_nanos(nanos.parse().ok()?),
+            })
+        })
+        .collect()
+}
+
+fn write_history(history: &[Record]) {
+    let content = history
+        .iter()
+        .map(|record| {
+            let (day, part, variant) = &record.key;
+            let nanos = record.median.as_nanos();
+            format!("{}\t{day}\t{part}\t{variant}\t{nanos}\n", record.commit)
+        })
+        .collect::<String>();
+    std::fs::write(HISTORY_FILE, content).expect("Failed to write the history file");
+}
+
+/// Reads the tree tables printed by divan :
+/// ```text
+/// day13                 fastest       │ slowest       │ median        │ mean          │ samples │ iters
+/// ├─ part1                            │               │               │               │         │
+/// │  ├─ rayon           300.1 µs      │ 1.02 ms       │ 387.4 µs      │ 410.2 µs      │ 100     │ 100
+/// ```
+#[derive(Debug, Default)]
+struct DivanParser {
+    day: String,
+    path: Vec<String>,
+}
+impl DivanParser {
+    fn parse_line(&mut self, line: &str) -> Option<(Key, Duration)> {
+        // The tree branches also use '│' : columns start after them
+        let branch = line.find(['├', '╰']);
+        let columns = line[branch.unwrap_or(0)..]
+            .split('│')
+            .map(str::trim)
+            .collect::<Vec<_>>();
+        if columns.len() < 3 {
+            return None;
+        }
+
+        let Some(branch) = branch else {
+            // Header of a new table
+            let (day, _) = columns[0].split_once(' ')?;
+            self.day = day.to_string();
+            self.path.clear();
+            return None;
+        };
+        // Every tree level is 3 characters wide
+        let depth = line[..branch].chars().count() / 3;
+        let name = columns[0]
+            .trim_start_matches(['├', '╰', '─', ' '])
+            .split_whitespace()
+            .next()?;
+        self.path.truncate(depth);
+        self.path.push(name.to_string());
+
+        let median = parse_duration(columns[2])?;
+        let (part, variant) = self.path.split_first()?;
+        let variant = if variant.is_empty() {
+            part.clone()
+        } else {
+            variant.join("::")
+        };
+        Some(((self.day.clone(), part.clone(), variant), median))
+    }
+}
+
+fn parse_duration(text: &str) -> Option<Duration> {
+    let (value, unit) = text.split_once(' ')?;
+    let value = value.parse::<f64>().ok()?;
+    let nanos = match unit {
+        "ps" => value / 1000.0,
+        "ns" => value,
+        "µs" | "us" => value * 1e3,
+        "ms" => value * 1e6,
+        "s" => value * 1e9,
+        _ => return None,
+    };
+    Some(Duration::from_nanos(nanos.round() as u64))
+}
+
+fn format_duration(duration: Duration) -> String {
+    let nanos = duration.as_nanos() as f64;
+    match nanos {
+        n if n < 1e3 => format!("{n:.0} ns"),
+        n if n < 1e6 => format!("{:.1} µs", n / 1e3),
+        n if n < 1e9 => format!("{:.2} ms", n / 1e6),
+        n => format!("{:.2} s", n / 1e9),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_divan_parser() {
+        let output = "\
+Timer precision: 20 ns
+day13                 fastest       │ slowest       │ median        │ mean          │ samples │ iters
+├─ part1                            │               │               │               │         │
+│  ├─ rayon           300.1 µs      │ 1.02 ms       │ 387.4 µs      │ 410.2 µs      │ 100     │ 100
+│  ╰─ single_thread   1.1 ms        │ 1.5 ms        │ 1.2 ms        │ 1.2 ms        │ 100     │ 100
+╰─ part2              2 ms          │ 3 ms          │ 2.5 ms        │ 2.6 ms        │ 100     │ 100";
+        let mut parser = DivanParser::default();
+        let records = output
+            .lines()
+            .filter_map(|line| parser.parse_line(line))
+            .collect::<Vec<_>>();
+        let key = |part: &str, variant: &str| ("day13".into(), part.into(), variant.into());
+        assert_eq!(
+            records,
+            [
+                (key("part1", "rayon"), Duration::from_nanos(387_400)),
+                (key("part1", "single_thread"), Duration::from_micros(1_200)),
+                (key("part2", "part2"), Duration::from_micros(2_500)),
+            ]
+        );
+    }
+}
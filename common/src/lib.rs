@@ -6,20 +6,161 @@ pub trait Aoc {
     type OUTPUT: PartialEq + std::fmt::Display + std::fmt::Debug;
 
     fn main() {
-        let input = Self::INPUT;
+        let input = input::tidy(Self::INPUT);
         println!("Day {:02} :", Self::DAY_NUMBER);
-        println!("Part 1 : {}", Self::part1(input));
-        println!("Part 2 : {}", Self::part2(input));
+        println!("Part 1 : {}", Self::part1(&input));
+        println!("Part 2 : {}", Self::part2(&input));
     }
     fn part1(input: &str) -> Self::OUTPUT;
     fn part2(input: &str) -> Self::OUTPUT;
 
+    /// Runs the sample with LF and CRLF line endings, then with a BOM and trailing whitespace,
+    /// tidied like the real input
     fn test_part1(expected: Self::OUTPUT) {
-        let input = Self::SAMPLE_PART1;
-        assert_eq!(expected, Self::part1(input));
+        for (variant, input) in input::variants(Self::SAMPLE_PART1) {
+            let input = input::tidy(&input);
+            assert_eq!(expected, Self::part1(&input), "with {variant}");
+        }
     }
+    /// Runs the sample with LF and CRLF line endings, then with a BOM and trailing whitespace,
+    /// tidied like the real input
     fn test_part2(expected: Self::OUTPUT) {
-        let input = Self::SAMPLE_PART2;
-        assert_eq!(expected, Self::part2(input));
+        for (variant, input) in input::variants(Self::SAMPLE_PART2) {
+            let input = input::tidy(&input);
+            assert_eq!(expected, Self::part2(&input), "with {variant}");
+        }
+    }
+}
+
+/// Access to the puzzle inputs, whatever the line endings, trailing whitespace, or byte order mark
+pub mod input {
+    /// Removes the UTF-8 byte order mark some editors add at the start of files
+    pub fn strip_bom(input: &str) -> &str {
+        input.strip_prefix('\u{feff}').unwrap_or(input)
+    }
+
+    /// Lines without their LF or CRLF ending and trailing whitespace.
+    /// Blank lines at the end of the input are skipped
+    pub fn lines(input: &str) -> impl Iterator<Item = &str> {
+        strip_bom(input).trim_end().lines().map(str::trim_end)
+    }
+
+    /// Blocks of lines separated by blank lines, of either line ending.
+    /// Blocks keep their inner line endings, without the trailing line ending and whitespace
+    pub fn blocks(input: &str) -> impl Iterator<Item = &str> {
+        let input = strip_bom(input).trim_end();
+        let mut rest = Some(input);
+        std::iter::from_fn(move || loop {
+            let remaining = rest?;
+            let (block, next) = split_blank_line(remaining);
+            rest = next;
+            let block = block.trim_end();
+            // Consecutive blank lines do not make empty blocks
+            if !block.trim().is_empty() {
+                return Some(block.trim_start_matches(['\r', '\n']));
+            }
+        })
+    }
+
+    /// Splits at the first blank line, which may contain whitespace
+    fn split_blank_line(input: &str) -> (&str, Option<&str>) {
+        let mut start = 0;
+        while let Some(offset) = input[start..].find('\n') {
+            let line_end = start + offset + 1;
+            let next_line = input[line_end..].split_inclusive('\n').next();
+            if let Some(line) = next_line.filter(|line| line.trim().is_empty()) {
+                return (
+                    &input[..start + offset],
+                    Some(&input[line_end + line.len()..]),
+                );
+            }
+            start = line_end;
+        }
+        (input, None)
+    }
+
+    /// Text with LF line endings only, no trailing whitespace nor byte order mark,
+    /// ending with a single line ending
+    pub fn normalise(input: &str) -> String {
+        let mut output = String::new();
+        for line in lines(input) {
+            output.push_str(line);
+            output.push('\n');
+        }
+        output
+    }
+
+    /// Lines without trailing whitespace nor byte order mark, each ending with the line ending
+    /// of the input : CRLF if it has any, LF otherwise
+    pub fn tidy(input: &str) -> String {
+        let ending = if input.contains("\r\n") { "\r\n" } else { "\n" };
+        let mut output = String::new();
+        for line in lines(input) {
+            output.push_str(line);
+            output.push_str(ending);
+        }
+        output
+    }
+
+    /// The same text with LF, then with CRLF line endings
+    pub fn line_ending_variants(input: &str) -> [(&'static str, String); 2] {
+        let lf = input.replace("\r\n", "\n");
+        let crlf = lf.replace('\n', "\r\n");
+        [("LF", lf), ("CRLF", crlf)]
+    }
+
+    /// The line ending variants, then CRLF with a byte order mark, trailing whitespace and blank lines
+    pub fn variants(input: &str) -> [(&'static str, String); 3] {
+        let [lf, crlf] = line_ending_variants(input);
+        let mut messy = String::from('\u{feff}');
+        for line in crlf.1.lines() {
+            messy.push_str(line);
+            messy.push_str(" \t\r\n");
+        }
+        messy.push_str("\r\n  \r\n");
+        [lf, crlf, ("a BOM and trailing whitespace", messy)]
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_lines() {
+            let input = "\u{feff}ab  \r\ncd\n\n  ef\t\r\n\r\n \n";
+            assert_eq!(
+                vec!["ab", "cd", "", "  ef"],
+                lines(input).collect::<Vec<_>>()
+            );
+            assert_eq!("ab\ncd\n\n  ef\n", normalise(input));
+            assert_eq!(0, lines("").count());
+        }
+
+        #[test]
+        fn test_blocks() {
+            let lf = "#.#\n..#\n\n##.\n#..\n";
+            let crlf = "\u{feff}#.#\r\n..#\r\n \r\n\r\n##.\r\n#..  \r\n\r\n";
+            assert_eq!(vec!["#.#\n..#", "##.\n#.."], blocks(lf).collect::<Vec<_>>());
+            let blocks = blocks(crlf).collect::<Vec<_>>();
+            assert_eq!(vec!["#.#\r\n..#", "##.\r\n#.."], blocks);
+            assert_eq!(vec!["#.#", "..#"], blocks[0].lines().collect::<Vec<_>>());
+        }
+
+        #[test]
+        fn test_line_ending_variants() {
+            let [(_, lf), (_, crlf)] = line_ending_variants("a\r\nb\nc");
+            assert_eq!("a\nb\nc", lf);
+            assert_eq!("a\r\nb\r\nc", crlf);
+        }
+
+        #[test]
+        fn test_tidy() {
+            let [(_, lf), (_, crlf), (_, messy)] = variants("a b\nc\n");
+            assert_eq!("\u{feff}a b \t\r\nc \t\r\n\r\n  \r\n", messy);
+            assert_eq!("a b\nc\n", tidy(&lf));
+            assert_eq!("a b\r\nc\r\n", tidy(&crlf));
+            assert_eq!("a b\r\nc\r\n", tidy(&messy));
+            assert_eq!("", tidy("\u{feff} \n"));
+        }
     }
 }